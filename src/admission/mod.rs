@@ -0,0 +1,195 @@
+use hyper::server::conn::Http;
+use k8s_openapi::api::{
+    admissionregistration::v1::{MutatingWebhook, ValidatingWebhook},
+    apps::v1::Deployment,
+    core::v1::Pod,
+};
+use kube::core::{
+    admission::{AdmissionRequest, AdmissionResponse, AdmissionReview},
+    DynamicObject,
+};
+use kube::Client;
+use rustls::ServerConfig;
+use serde_json::Value;
+use std::convert::{From, Infallible};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio_rustls::TlsAcceptor;
+use tracing::{info, warn};
+use warp::{reply, Filter, Reply};
+
+use crate::controller::Error;
+use crate::crd::WebhookHelper;
+use crate::operator::{validate_certificate, validate_container_name};
+use crate::{health, metrics};
+pub use tls::ClientCa;
+use tls::{client_verifier, ReloadingCertResolver, CERT_PATH, KEY_PATH};
+
+mod tls;
+
+/// How often the mounted certificate is checked for changes
+const TLS_RELOAD_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Serve `/validate`, requiring client certificates when `client_ca` is set
+pub async fn serve(port: u16, client_ca: Option<ClientCa>) -> Result<(), Error> {
+    let client = crate::client().await?;
+    let verifier = match client_ca.as_ref() {
+        Some(ca) => Some(client_verifier(client.clone(), ca).await?),
+        None => None,
+    };
+
+    let routes = warp::path("validate")
+        .and(warp::post())
+        .and(warp::body::json())
+        .and_then(move |body: AdmissionReview<DynamicObject>| handler(client.clone(), body))
+        .with(warp::trace::request());
+    let service = warp::service(warp::post().and(routes));
+
+    let resolver = Arc::new(ReloadingCertResolver::load(CERT_PATH, KEY_PATH)?);
+    tokio::spawn(resolver.clone().watch(TLS_RELOAD_INTERVAL));
+
+    let builder = ServerConfig::builder();
+    let mut config = match verifier {
+        Some(verifier) => builder.with_client_cert_verifier(verifier),
+        None => builder.with_no_client_auth(),
+    }
+    .with_cert_resolver(resolver);
+    config.alpn_protocols = vec![b"h2".to_vec(), b"http/1.1".to_vec()];
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+
+    let listener = TcpListener::bind(("0.0.0.0", port))
+        .await
+        .map_err(|e| Error::UnableToConfigureTls(e.to_string()))?;
+    health::set_tls_loaded();
+    loop {
+        let (stream, peer) = match listener.accept().await {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                warn!("Unable to accept connection: {}", e);
+                continue;
+            }
+        };
+
+        let acceptor = acceptor.clone();
+        let service = service.clone();
+        tokio::spawn(async move {
+            match acceptor.accept(stream).await {
+                Ok(tls_stream) => {
+                    if let Err(e) = Http::new().serve_connection(tls_stream, service).await {
+                        warn!("Error serving connection: {}", e);
+                    };
+                }
+                // includes clients rejected for a missing or untrusted certificate
+                Err(e) => warn!("Rejected TLS connection from {}: {}", peer, e),
+            };
+        });
+    }
+}
+
+#[allow(unused_variables)]
+async fn handler(
+    client: Client,
+    body: AdmissionReview<DynamicObject>,
+) -> Result<impl Reply, Infallible> {
+    // Parse incoming webhook AdmissionRequest first
+    let req: AdmissionRequest<_> = match body.try_into() {
+        Ok(req) => req,
+        Err(err) => {
+            return Ok(review(
+                AdmissionResponse::invalid(err.to_string()),
+                "InvalidReview",
+            ));
+        }
+    };
+
+    let mut res = AdmissionResponse::from(&req);
+    let raw: Value = match req.object {
+        Some(o) => {
+            if let Ok(r) = serde_json::to_value(o) {
+                r
+            } else {
+                res = res.deny("invalid request format".to_string().to_string());
+                return Ok(review(res, "InvalidRequest"));
+            }
+        }
+        None => return Ok(review(res, "NoObject")),
+    };
+
+    let resource: WebhookHelper = match serde_json::from_value(raw) {
+        Ok(v) => v,
+        Err(_) => {
+            res = res.deny("invalid request format".to_string().to_string());
+            return Ok(review(res, "InvalidWebhookHelper"));
+        }
+    };
+
+    let deployment: Option<Deployment> =
+        serde_json::from_value(resource.spec.deployment.clone()).ok();
+
+    if let Some(d) = deployment.clone() {
+        match d.spec.clone() {
+            Some(deployment_spec) => {
+                if let Err(e) = validate_container_name(
+                    resource.spec.container_name.clone(),
+                    deployment_spec.template.spec.clone(),
+                )
+                .await
+                {
+                    res = res.deny(format!("{}", e));
+                    return Ok(review(res, "InvalidContainerName"));
+                }
+            }
+            None => {
+                res = res.deny(format!(
+                    "{}",
+                    Error::UnableToCreateObject("No DeploymentSpec found in WebhookHelper".into())
+                ));
+                return Ok(review(res, "InvalidDeployment"));
+            }
+        }
+    };
+
+    let pod: Option<Pod> = serde_json::from_value(resource.spec.deployment.clone()).ok();
+
+    if let Some(p) = pod.clone() {
+        if let Err(e) =
+            validate_container_name(resource.spec.container_name.clone(), p.spec.clone()).await
+        {
+            res = res.deny(format!("{}", e));
+            return Ok(review(res, "InvalidContainerName"));
+        };
+    };
+
+    if deployment.is_none() && pod.is_none() {
+        res = res.deny("invalid request format".to_string().to_string());
+        return Ok(review(res, "InvalidDeployment"));
+    };
+
+    if let Err(e) = validate_certificate(&resource.spec).await {
+        res = res.deny(format!("{}", e));
+        return Ok(review(res, "InvalidCertificate"));
+    };
+
+    let validating_webhook: Option<ValidatingWebhook> =
+        serde_json::from_value(resource.spec.webhook.clone()).ok();
+
+    let mutating_webhook: Option<MutatingWebhook> =
+        serde_json::from_value(resource.spec.webhook).ok();
+
+    if validating_webhook.is_none() && mutating_webhook.is_none() {
+        res = res.deny("invalid request format".to_string().to_string());
+        return Ok(review(res, "InvalidWebhook"));
+    };
+
+    info!("Webhook helper validated");
+
+    // Wrap the AdmissionResponse wrapped in an AdmissionReview
+    Ok(review(res, "Valid"))
+}
+
+/// Wrap `res` in an AdmissionReview, counting it under `reason`
+fn review(res: AdmissionResponse, reason: &str) -> reply::Json {
+    metrics::admission_request(res.allowed, reason);
+    reply::json(&res.into_review())
+}
@@ -0,0 +1,152 @@
+use k8s_openapi::api::core::v1::ConfigMap;
+use kube::Client;
+use rustls::crypto::ring::sign::any_supported_type;
+use rustls::server::danger::ClientCertVerifier;
+use rustls::server::{ClientHello, ResolvesServerCert, WebPkiClientVerifier};
+use rustls::sign::CertifiedKey;
+use rustls::RootCertStore;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::controller::Error;
+use crate::operator::perform_get;
+
+pub const CERT_PATH: &str = "/webhook-helper/tls.crt";
+pub const KEY_PATH: &str = "/webhook-helper/tls.key";
+
+/// Where the CA trusted to sign admission client certificates is read from
+pub enum ClientCa {
+    File(String),
+    ConfigMap {
+        namespace: String,
+        name: String,
+        key: String,
+    },
+}
+
+/// Require client certificates signed by `client_ca`
+pub async fn client_verifier(
+    client: Client,
+    client_ca: &ClientCa,
+) -> Result<Arc<dyn ClientCertVerifier>, Error> {
+    let tls_error = |e: std::io::Error| Error::UnableToConfigureTls(e.to_string());
+
+    let (source, pem) = match client_ca {
+        ClientCa::File(path) => (path.clone(), std::fs::read(path).map_err(tls_error)?),
+        ClientCa::ConfigMap {
+            namespace,
+            name,
+            key,
+        } => {
+            let config_map: ConfigMap = perform_get(client, name, namespace).await?;
+            let pem = config_map
+                .data
+                .and_then(|d| d.get(key).cloned())
+                .ok_or_else(|| {
+                    Error::UnableToConfigureTls(format!(
+                        "ConfigMap {} does not contain {}",
+                        name, key
+                    ))
+                })?;
+            (
+                format!("ConfigMap {}/{}", namespace, name),
+                pem.into_bytes(),
+            )
+        }
+    };
+
+    let mut roots = RootCertStore::empty();
+    for cert in rustls_pemfile::certs(&mut pem.as_slice()) {
+        roots
+            .add(cert.map_err(tls_error)?)
+            .map_err(|e| Error::UnableToConfigureTls(e.to_string()))?;
+    }
+    if roots.is_empty() {
+        return Err(Error::UnableToConfigureTls(format!(
+            "No client CA found in {}",
+            source
+        )));
+    };
+
+    info!(
+        "Requiring admission client certificates signed by {}",
+        source
+    );
+    WebPkiClientVerifier::builder(Arc::new(roots))
+        .build()
+        .map_err(|e| Error::UnableToConfigureTls(e.to_string()))
+}
+
+/// Serves the mounted certificate and swaps it when the secret volume is updated
+///
+/// Only new handshakes pick up a reloaded certificate, open connections keep theirs.
+#[derive(Debug)]
+pub struct ReloadingCertResolver {
+    cert_path: String,
+    key_path: String,
+    current: RwLock<(Vec<u8>, Arc<CertifiedKey>)>,
+}
+
+impl ReloadingCertResolver {
+    pub fn load(cert_path: &str, key_path: &str) -> Result<ReloadingCertResolver, Error> {
+        let (pem, key) = read_certified_key(cert_path, key_path)?;
+        Ok(ReloadingCertResolver {
+            cert_path: cert_path.into(),
+            key_path: key_path.into(),
+            current: RwLock::new((pem, Arc::new(key))),
+        })
+    }
+
+    /// Re-read the files every `interval`, keeping the previous certificate if they cannot be parsed
+    pub async fn watch(self: Arc<Self>, interval: Duration) {
+        loop {
+            tokio::time::sleep(interval).await;
+            match read_certified_key(&self.cert_path, &self.key_path) {
+                Ok((pem, key)) => {
+                    let mut current = self.current.write().unwrap();
+                    if current.0 != pem {
+                        *current = (pem, Arc::new(key));
+                        info!("Reloaded TLS certificate from {}", self.cert_path);
+                    };
+                }
+                // the kubelet swaps the files one at a time
+                Err(e) => warn!("Unable to reload TLS certificate: {}", e),
+            };
+        }
+    }
+}
+
+impl ResolvesServerCert for ReloadingCertResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        Some(self.current.read().unwrap().1.clone())
+    }
+}
+
+fn read_certified_key(cert_path: &str, key_path: &str) -> Result<(Vec<u8>, CertifiedKey), Error> {
+    let tls_error = |e: std::io::Error| Error::UnableToConfigureTls(e.to_string());
+
+    let cert_pem = std::fs::read(cert_path).map_err(tls_error)?;
+    let key_pem = std::fs::read(key_path).map_err(tls_error)?;
+
+    let certs = rustls_pemfile::certs(&mut cert_pem.as_slice())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(tls_error)?;
+    if certs.is_empty() {
+        return Err(Error::UnableToConfigureTls(format!(
+            "No certificate found in {}",
+            cert_path
+        )));
+    };
+    let key = rustls_pemfile::private_key(&mut key_pem.as_slice())
+        .map_err(tls_error)?
+        .ok_or_else(|| {
+            Error::UnableToConfigureTls(format!("No private key found in {}", key_path))
+        })?;
+    let signing_key =
+        any_supported_type(&key).map_err(|e| Error::UnableToConfigureTls(e.to_string()))?;
+
+    let mut contents = cert_pem;
+    contents.extend(key_pem);
+    Ok((contents, CertifiedKey::new(certs, signing_key)))
+}
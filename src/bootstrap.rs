@@ -6,20 +6,37 @@ use k8s_openapi::api::admissionregistration::v1::{
     ValidatingWebhook, ValidatingWebhookConfiguration,
 };
 use k8s_openapi::api::apps::v1::{Deployment, DeploymentSpec};
-use k8s_openapi::api::core::v1::{Container, PodSpec, PodTemplateSpec};
+use k8s_openapi::api::core::v1::{
+    Container, EnvVar, EnvVarSource, HTTPGetAction, ObjectFieldSelector, PodSpec, PodTemplateSpec,
+    Probe,
+};
 use k8s_openapi::apimachinery::pkg::apis::meta::v1::LabelSelector;
+use k8s_openapi::apimachinery::pkg::util::intstr::IntOrString;
 use kube::core::ObjectMeta;
 use kube::Client;
-use serde_json::Value;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tracing::{info, warn};
 
 use k8s_openapi::api::core::v1::ContainerPort;
 
 use crate::controller::Error;
 use crate::crd::{HelperSpec, WebhookHelper};
-use crate::operator;
+use crate::operator::{self, Operation};
+
+const CONTAINER_PORT: i32 = 9443;
+/// Longest the renewal loop sleeps, so a deleted secret is noticed
+const RENEWAL_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+const RENEWAL_RETRY_INTERVAL: Duration = Duration::from_secs(60);
 
-pub async fn bootstrap(namespace: String) -> Result<(), Error> {
-    let container_port = 9443;
+pub async fn bootstrap(
+    namespace: String,
+    replicas: i32,
+    lease_duration: u64,
+    lease_renew_period: u64,
+) -> Result<(), Error> {
+    let container_port = CONTAINER_PORT;
+    let metrics_port: u16 = 8080;
     let client = Client::try_default().await?;
 
     let mut label_map: BTreeMap<String, String> = BTreeMap::new();
@@ -44,17 +61,46 @@ pub async fn bootstrap(namespace: String) -> Result<(), Error> {
                             "run".into(),
                             "-p".into(),
                             format!("{}", container_port),
+                            "-n".into(),
+                            namespace.clone(),
+                            "--metrics-port".into(),
+                            format!("{}", metrics_port),
+                            "--lease-duration".into(),
+                            format!("{}", lease_duration),
+                            "--lease-renew-period".into(),
+                            format!("{}", lease_renew_period),
                         ]),
+                        env: Some(vec![EnvVar {
+                            name: "POD_NAME".into(),
+                            value_from: Some(EnvVarSource {
+                                field_ref: Some(ObjectFieldSelector {
+                                    field_path: "metadata.name".into(),
+                                    ..Default::default()
+                                }),
+                                ..Default::default()
+                            }),
+                            ..Default::default()
+                        }]),
                         #[cfg(feature = "local")]
                         image: Some("webhook-helper:latest".to_string()),
                         #[cfg(not(feature = "local"))]
                         image: Some("rc1405/webhook-helper:latest".to_string()),
-                        ports: Some(vec![ContainerPort {
-                            container_port,
-                            protocol: Some("TCP".into()),
-                            ..Default::default()
-                        }]),
+                        ports: Some(vec![
+                            ContainerPort {
+                                container_port,
+                                protocol: Some("TCP".into()),
+                                ..Default::default()
+                            },
+                            ContainerPort {
+                                name: Some("metrics".into()),
+                                container_port: metrics_port.into(),
+                                protocol: Some("TCP".into()),
+                                ..Default::default()
+                            },
+                        ]),
                         name: String::from("webhook-helper"),
+                        liveness_probe: Some(http_probe("/healthz", metrics_port)),
+                        readiness_probe: Some(http_probe("/readyz", metrics_port)),
                         #[cfg(feature = "local")]
                         image_pull_policy: Some("Never".into()),
                         ..Default::default()
@@ -63,7 +109,7 @@ pub async fn bootstrap(namespace: String) -> Result<(), Error> {
                     ..Default::default()
                 }),
             },
-            replicas: Some(1),
+            replicas: Some(replicas),
             selector: LabelSelector {
                 match_labels: Some(label_map),
                 ..Default::default()
@@ -104,24 +150,94 @@ pub async fn bootstrap(namespace: String) -> Result<(), Error> {
 
     operator::bootstrap::bootstrap(
         client.clone(),
-        WebhookHelper {
-            metadata: ObjectMeta {
-                name: Some(format!("webhook-helper.{}.svc", namespace.to_lowercase())),
-                namespace: Some(namespace.clone()),
-                ..Default::default()
-            },
-            spec: HelperSpec {
-                namespace: namespace.clone(),
-                webhook: webhook_value,
-                listening_port: container_port,
-                target_port: Some(container_port),
-                path: Some("/validate".into()),
-                deployment: deployment_value,
-                container_name: Some("webhook-helper".into()),
-            },
-            status: None,
-        },
+        operator_helper(&namespace, deployment_value, webhook_value),
     )
     .await?;
     Ok(())
 }
+
+/// The helper the operator deploys itself through, it only exists in memory
+fn operator_helper(namespace: &str, deployment: Value, webhook: Value) -> WebhookHelper {
+    WebhookHelper {
+        metadata: ObjectMeta {
+            name: Some(format!("webhook-helper.{}.svc", namespace.to_lowercase())),
+            namespace: Some(namespace.to_string()),
+            ..Default::default()
+        },
+        spec: HelperSpec {
+            namespace: namespace.to_string(),
+            webhook,
+            listening_port: CONTAINER_PORT,
+            target_port: Some(CONTAINER_PORT),
+            path: Some("/validate".into()),
+            deployment,
+            container_name: Some("webhook-helper".into()),
+            certificate: None,
+            issuer: None,
+            tls_secret: None,
+            force_apply: None,
+        },
+        status: None,
+    }
+}
+
+/// Re-issue the operator's own serving certificate once it enters its renewal window
+///
+/// No `WebhookHelper` object exists for the operator, so the controller never renews it.
+pub async fn renew_certificate(namespace: String) -> Result<(), Error> {
+    let client = crate::client().await?;
+    // only the deployment name is needed to issue the certificate
+    let helper = operator_helper(
+        &namespace,
+        json!({ "metadata": { "name": "webhook-helper" } }),
+        Value::Null,
+    );
+    loop {
+        let mut cert = operator::CertificateStage::new(
+            client.clone(),
+            Operation::Bootstrap,
+            helper.clone(),
+            namespace.clone(),
+        );
+        let wait = match cert.secret_missing().await {
+            Ok(true) => Ok(Duration::ZERO),
+            Ok(false) => cert.time_until_renewal().await,
+            Err(e) => Err(e),
+        };
+        match wait {
+            Ok(wait) if !wait.is_zero() => {
+                info!("Operator certificate renewal in {}s", wait.as_secs());
+                tokio::time::sleep(wait.min(RENEWAL_CHECK_INTERVAL)).await;
+                continue;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                warn!("Unable to check the operator certificate: {:?}", e);
+                tokio::time::sleep(RENEWAL_RETRY_INTERVAL).await;
+                continue;
+            }
+        };
+
+        info!("Renewing the operator certificate");
+        match cert.run().await {
+            Ok(_) => info!("Operator certificate renewed"),
+            Err(e) => {
+                warn!("Unable to renew the operator certificate: {:?}", e);
+                tokio::time::sleep(RENEWAL_RETRY_INTERVAL).await;
+            }
+        };
+    }
+}
+
+fn http_probe(path: &str, port: u16) -> Probe {
+    Probe {
+        http_get: Some(HTTPGetAction {
+            path: Some(path.into()),
+            port: IntOrString::Int(port.into()),
+            ..Default::default()
+        }),
+        period_seconds: Some(10),
+        failure_threshold: Some(3),
+        ..Default::default()
+    }
+}
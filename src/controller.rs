@@ -1,10 +1,18 @@
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::admissionregistration::v1::{
+    MutatingWebhookConfiguration, ValidatingWebhookConfiguration,
+};
+use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::core::v1::{Pod, Secret, Service};
 use kube::api::{Patch, PatchParams};
-use kube::core::ResourceExt;
+use kube::core::{Resource, ResourceExt};
 use kube::runtime::controller::Action;
+use kube::runtime::events::EventType;
 use kube::{Api, Client};
 use serde_json::{json, Value};
-use std::sync::Arc;
-use std::time::Duration;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use thiserror::Error;
 use tracing::{error, info, warn};
 
@@ -13,8 +21,17 @@ use kube::runtime::{controller::Controller, watcher, Config};
 
 use crate::crd::{Stage, WebhookHelper};
 use crate::operator::{
-    determine_stage, CertificateStage, DeploymentStage, Operation, ServiceStage, WebhookStage,
+    clear_creation_failure, determine_stage, patch_status, publish_event, serving, update_failures,
+    update_object_not_owned, update_status, CertificateStage, DeploymentStage, Operation,
+    ServiceStage, WebhookStage, MANAGED_BY_LABEL,
 };
+use crate::{health, metrics};
+
+/// Annotation whose new values re-attempt a certificate the signer refused
+pub const RETRY_ANNOTATION: &str = "webhook-helper.io/retry-certificate";
+
+/// Annotation whose new values force an immediate certificate rotation
+pub const ROTATE_ANNOTATION: &str = "webhook-helper.io/rotate-certificate";
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -28,17 +45,79 @@ pub enum Error {
     #[error("UnableToApproveCertificate: {0}")]
     UnableToApproveCertificate(#[from] http::Error),
     #[error("UnableToCreateCertificate: {0}")]
-    UnableToCreateCertificate(#[from] rcgen::RcgenError),
+    UnableToCreateCertificate(#[from] rcgen::Error),
     #[error("UnknownOperation: {0}")]
     UnknownOperation(String),
     #[error("ResourceNotReady")]
     ResourceNotReady,
     #[error("UnableToDetermineContainerName")]
     UnableToDetermineContainerName,
+    #[error("UnableToGenerateKey: {0}")]
+    UnableToGenerateKey(String),
+    #[error("CertificateRejected: {0}: {1}")]
+    CertificateRejected(String, String),
+    #[error("CertificateNotIssued: {0}")]
+    CertificateNotIssued(String),
+    #[error("UnableToParseCertificate: {0}")]
+    UnableToParseCertificate(String),
+    #[error("UnableToConfigureTls: {0}")]
+    UnableToConfigureTls(String),
+    #[error("ApplyConflict: {0}: {1}")]
+    ApplyConflict(String, String),
+    #[error("UnableToWaitForResource: {0}")]
+    UnableToWaitForResource(#[from] kube::runtime::wait::Error),
+    #[error("LeaseLost: {0}")]
+    LeaseLost(String),
+    #[error("ObjectNotOwned: {0} {1} {2}")]
+    ObjectNotOwned(String, String, String),
+}
+
+impl Error {
+    /// Stable reason for the event recorded when a reconcile fails
+    pub fn reason(&self) -> &'static str {
+        match self {
+            Error::WebhookHelperCreationFailed(_) => "WebhookHelperCreationFailed",
+            Error::UnableToCreateObject(_) => "UnableToCreateObject",
+            Error::UnableToSerializeObject(_) => "UnableToSerializeObject",
+            Error::UnableToApproveCertificate(_) => "UnableToApproveCertificate",
+            Error::UnableToCreateCertificate(_) => "UnableToCreateCertificate",
+            Error::UnknownOperation(_) => "UnknownOperation",
+            Error::ResourceNotReady => "ResourceNotReady",
+            Error::UnableToDetermineContainerName => "UnableToDetermineContainerName",
+            Error::UnableToGenerateKey(_) => "UnableToGenerateKey",
+            Error::CertificateRejected(_, _) => "CertificateRejected",
+            Error::CertificateNotIssued(_) => "CertificateNotIssued",
+            Error::UnableToParseCertificate(_) => "UnableToParseCertificate",
+            Error::UnableToConfigureTls(_) => "UnableToConfigureTls",
+            Error::ApplyConflict(_, _) => "ApplyConflict",
+            Error::UnableToWaitForResource(_) => "UnableToWaitForResource",
+            Error::LeaseLost(_) => "LeaseLost",
+            Error::ObjectNotOwned(_, _, _) => "ObjectNotOwned",
+        }
+    }
+}
+
+/// First delay after a failed reconcile, doubled with every failure in a row
+const BACKOFF_BASE: Duration = Duration::from_secs(5);
+const BACKOFF_MAX: Duration = Duration::from_secs(300);
+/// Longest a ready helper waits before its certificate is checked again, requeues far in the
+/// future are not accepted by the scheduler
+const RENEWAL_CHECK_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// How often a certificate that could not be created is attempted again
+pub struct RetryPolicy {
+    pub max_creation_retries: u32,
+    /// Wait after a failed attempt before the next one
+    pub creation_retry_cooldown: Duration,
 }
 
 struct CustomClients {
     kube: Client,
+    /// Namespace the operator runs in
+    namespace: String,
+    retry: RetryPolicy,
+    /// Delay for `error_policy` per helper, set when its reconcile fails
+    backoff: Mutex<HashMap<String, Duration>>,
 }
 
 enum CustomAction {
@@ -58,7 +137,7 @@ enum CustomAction {
 /// # Arguments
 /// - `echo`: A reference to `Echo` being reconciled to decide next action upon.
 fn determine_action<T: kube::core::Resource>(echo: &T) -> CustomAction {
-    return if echo.meta().deletion_timestamp.is_some() {
+    if echo.meta().deletion_timestamp.is_some() {
         echo.meta()
             .finalizers
             .as_ref()
@@ -88,11 +167,146 @@ fn determine_action<T: kube::core::Resource>(echo: &T) -> CustomAction {
                 };
                 CustomAction::Create
             })
-    };
+    }
+}
+
+impl CustomAction {
+    /// Label of the action in metrics
+    fn label(&self) -> &'static str {
+        match self {
+            CustomAction::Create => "create",
+            CustomAction::Delete => "delete",
+            CustomAction::Update => "update",
+            CustomAction::NoOp => "noop",
+        }
+    }
 }
 
 /// The reconciler that will be called when either object change
 async fn reconcile(g: Arc<WebhookHelper>, ctx: Arc<CustomClients>) -> Result<Action, Error> {
+    let action = determine_action(&*g).label();
+    let started = Instant::now();
+    if g.meta().deletion_timestamp.is_none() {
+        metrics::observe_helper(&g);
+    };
+    let result = report(g, ctx).await;
+    let outcome = match result {
+        Ok(_) => "success",
+        Err(_) => "error",
+    };
+    metrics::reconciled(action, outcome, started.elapsed());
+    result
+}
+
+/// Record apply conflicts and failures in status and as events, holding back helpers that are backing off
+async fn report(g: Arc<WebhookHelper>, ctx: Arc<CustomClients>) -> Result<Action, Error> {
+    let status = g.status.clone().unwrap_or_default();
+    let deleting = g.meta().deletion_timestamp.is_some();
+    // a spec change may fix what failed, so it is not held back
+    let spec_changed = status.last_error_generation != g.meta().generation;
+    // recording a failure triggers a reconcile of its own
+    if let Some(wait) = status
+        .retry_after
+        .as_deref()
+        .and_then(parse_time)
+        .and_then(time_until)
+    {
+        if !deleting && !spec_changed {
+            return Ok(Action::requeue(wait));
+        };
+    };
+
+    let reported = status.apply_conflict.clone();
+    match reconcile_helper(g.clone(), ctx.clone()).await {
+        // another field manager owns something webhook-helper wants to set
+        Err(Error::ApplyConflict(object, message)) => {
+            let conflict = format!("{}: {}", object, message);
+            warn!("Unable to apply {} for {}", conflict, g.name_any());
+            if reported.as_ref() != Some(&conflict) {
+                patch_status(ctx.kube.clone(), &g, |status| {
+                    status.apply_conflict = Some(conflict.clone())
+                })
+                .await?;
+                publish_event(
+                    ctx.kube.clone(),
+                    &g,
+                    EventType::Warning,
+                    "ApplyConflict",
+                    "Apply",
+                    conflict,
+                )
+                .await;
+            };
+            Ok(Action::requeue(Duration::from_secs(300)))
+        }
+        Ok(action) => {
+            if reported.is_some() && !deleting {
+                let cleared =
+                    patch_status(ctx.kube.clone(), &g, |status| status.apply_conflict = None);
+                if let Err(e) = cleared.await {
+                    warn!(
+                        "Unable to clear apply conflict of {}: {:?}",
+                        g.name_any(),
+                        e
+                    );
+                };
+            };
+            if status.failures.is_some() && !deleting {
+                if let Err(e) = update_failures(ctx.kube.clone(), None, (*g).clone()).await {
+                    warn!("Unable to clear failures of {}: {:?}", g.name_any(), e);
+                };
+            };
+            Ok(action)
+        }
+        // recorded here as `error_policy` cannot publish
+        Err(e) => {
+            if let Error::ObjectNotOwned(kind, name, reason) = &e {
+                let message = format!("{} {} {}", kind, name, reason);
+                if let Err(status_error) =
+                    update_object_not_owned(ctx.kube.clone(), kind, message, (*g).clone()).await
+                {
+                    warn!(
+                        "Unable to record refused {} for {}: {:?}",
+                        name,
+                        g.name_any(),
+                        status_error
+                    );
+                };
+            };
+            let failures = status.failures.unwrap_or(0) + 1;
+            let delay = backoff(failures);
+            ctx.backoff.lock().unwrap().insert(g.name_any(), delay);
+            // a helper being deleted is not held back, so its status is left alone
+            if !deleting {
+                let retry_after =
+                    Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default();
+                let failure = Some((failures, e.to_string(), retry_after));
+                if let Err(status_error) =
+                    update_failures(ctx.kube.clone(), failure, (*g).clone()).await
+                {
+                    warn!(
+                        "Unable to record failure of {}: {:?}",
+                        g.name_any(),
+                        status_error
+                    );
+                };
+            };
+            publish_event(
+                ctx.kube.clone(),
+                &g,
+                EventType::Warning,
+                e.reason(),
+                "Reconcile",
+                e.to_string(),
+            )
+            .await;
+            Err(e)
+        }
+    }
+}
+
+/// Drive the helper through its stages
+async fn reconcile_helper(g: Arc<WebhookHelper>, ctx: Arc<CustomClients>) -> Result<Action, Error> {
     // .. use api here to reconcile a child ConfigMap with ownerreferences
     // see configmapgen_controller example for full info
     let webhook_api: Api<WebhookHelper> = Api::all(ctx.kube.clone());
@@ -119,8 +333,24 @@ async fn reconcile(g: Arc<WebhookHelper>, ctx: Arc<CustomClients>) -> Result<Act
         match determine_action(&z) {
             CustomAction::Create => {
                 info!("Creating webhook {}", z.name_any());
-                let mut cert_stage =
-                    CertificateStage::new(ctx.kube.clone(), Operation::Create, z.clone());
+                if z.status.is_none() {
+                    let created = Stage::HelperCreated;
+                    publish_event(
+                        ctx.kube.clone(),
+                        &z,
+                        EventType::Normal,
+                        &created.to_string(),
+                        "Create",
+                        created.message(),
+                    )
+                    .await;
+                };
+                let mut cert_stage = CertificateStage::new(
+                    ctx.kube.clone(),
+                    Operation::Create,
+                    z.clone(),
+                    ctx.namespace.clone(),
+                );
                 match cert_stage.run().await {
                     Ok(_) => {
                         let finalizer: Value = json!({
@@ -139,6 +369,9 @@ async fn reconcile(g: Arc<WebhookHelper>, ctx: Arc<CustomClients>) -> Result<Act
             }
             CustomAction::Delete => {
                 info!("Deleting webhook {}", z.name_any());
+                if z.status.as_ref().and_then(|s| s.stage.as_deref()) != Some("Deleting") {
+                    update_status(ctx.kube.clone(), Stage::Deleting, z.clone()).await?;
+                };
 
                 if let Some(status) = z.status.clone() {
                     if status.pod.is_some() || status.deployment.is_some() {
@@ -166,9 +399,13 @@ async fn reconcile(g: Arc<WebhookHelper>, ctx: Arc<CustomClients>) -> Result<Act
                         service_stage.run().await?;
                     };
 
-                    if status.certificate.is_some() {
-                        let mut cert_stage =
-                            CertificateStage::new(ctx.kube.clone(), Operation::Delete, z.clone());
+                    if status.certificate.is_some() || status.certificate_request.is_some() {
+                        let mut cert_stage = CertificateStage::new(
+                            ctx.kube.clone(),
+                            Operation::Delete,
+                            z.clone(),
+                            ctx.namespace.clone(),
+                        );
                         cert_stage.run().await?;
                     }
                 };
@@ -183,6 +420,7 @@ async fn reconcile(g: Arc<WebhookHelper>, ctx: Arc<CustomClients>) -> Result<Act
                 webhook_api
                     .patch(&name, &PatchParams::default(), &patch)
                     .await?;
+                metrics::forget_helper(&name);
 
                 return Ok(Action::await_change());
             }
@@ -202,8 +440,49 @@ async fn reconcile(g: Arc<WebhookHelper>, ctx: Arc<CustomClients>) -> Result<Act
                     return Ok(Action::requeue(Duration::from_secs(10)));
                 }
                 Stage::CreationFailed(_) => {
-                    info!("Creation failed for {}", z.name_any());
-                    return Ok(Action::await_change());
+                    let status = z.status.clone().unwrap_or_default();
+                    let spec_changed = status.failed_generation != z.meta().generation;
+                    let retries = match spec_changed {
+                        true => 0,
+                        false => status.creation_retries.unwrap_or(0),
+                    };
+                    if retries >= ctx.retry.max_creation_retries {
+                        info!(
+                            "Creation failed for {} after {} retries",
+                            z.name_any(),
+                            retries
+                        );
+                        return Ok(Action::await_change());
+                    };
+                    let cooldown = chrono::Duration::from_std(ctx.retry.creation_retry_cooldown)
+                        .unwrap_or_default();
+                    let retry_at = status
+                        .creation_failed_at
+                        .as_deref()
+                        .and_then(parse_time)
+                        .map(|t| t + cooldown);
+                    if let Some(wait) = retry_at.and_then(time_until) {
+                        if !spec_changed {
+                            info!(
+                                "Creation failed for {}, retrying in {}s",
+                                z.name_any(),
+                                wait.as_secs()
+                            );
+                            return Ok(Action::requeue(wait));
+                        };
+                    };
+
+                    info!(
+                        "Retrying creation for {} ({}/{})",
+                        z.name_any(),
+                        retries + 1,
+                        ctx.retry.max_creation_retries
+                    );
+                    patch_status(ctx.kube.clone(), &z, |status| {
+                        status.creation_retries = Some(retries + 1)
+                    })
+                    .await?;
+                    return continue_issuance(&ctx, &z).await;
                 }
                 Stage::DeploymentComplete(d) => {
                     info!("Deployment is complete for {}", z.name_any());
@@ -234,43 +513,280 @@ async fn reconcile(g: Arc<WebhookHelper>, ctx: Arc<CustomClients>) -> Result<Act
                     return Ok(Action::requeue(Duration::from_secs(10)));
                 }
                 Stage::WebhookCreated(_) => {
-                    info!("Webhook created {}", z.name_any());
-                    return Ok(Action::await_change());
+                    if reconcile_children(&ctx, &z).await? {
+                        info!("Waiting for the pod of {} to be deleted", z.name_any());
+                        return Ok(Action::requeue(Duration::from_secs(5)));
+                    };
+
+                    let mut cert_stage = CertificateStage::new(
+                        ctx.kube.clone(),
+                        Operation::Update,
+                        z.clone(),
+                        ctx.namespace.clone(),
+                    );
+                    if cert_stage.secret_missing().await? {
+                        info!("Secret for {} was deleted, re-issuing", z.name_any());
+                        cert_stage.run().await?;
+                        return complete_renewal(&ctx, &z, &cert_stage).await;
+                    };
+                    let renew_in = cert_stage.time_until_renewal().await?;
+                    if let Some(rotation) = requested_rotation(&z) {
+                        info!(
+                            "Rotating certificate for {} as requested at {}",
+                            z.name_any(),
+                            rotation
+                        );
+                        cert_stage.run().await?;
+                        return complete_renewal(&ctx, &z, &cert_stage).await;
+                    };
+                    if !renew_in.is_zero() {
+                        info!(
+                            "Webhook created {}, certificate renewal in {}s",
+                            z.name_any(),
+                            renew_in.as_secs()
+                        );
+                        return Ok(Action::requeue(renew_in.min(RENEWAL_CHECK_INTERVAL)));
+                    };
+
+                    info!("Renewing certificate for {}", z.name_any());
+                    cert_stage.run().await?;
+                    return complete_renewal(&ctx, &z, &cert_stage).await;
+                }
+                Stage::CsrCreated(_)
+                | Stage::CsrApproved(_)
+                | Stage::CertificateIssued(_)
+                | Stage::CertificateRequested(_) => {
+                    info!("Checking certificate request for {}", z.name_any());
+                    return continue_issuance(&ctx, &z).await;
+                }
+                Stage::CertificateFailed(_, _) => {
+                    let status = z.status.clone().unwrap_or_default();
+                    let retry = z.annotations().get(RETRY_ANNOTATION).cloned();
+                    let spec_changed = status.failed_generation != z.meta().generation;
+                    let retry_requested = retry.is_some() && retry != status.certificate_retry;
+                    if !spec_changed && !retry_requested {
+                        info!("Certificate was not issued for {}", z.name_any());
+                        return Ok(Action::await_change());
+                    };
+
+                    info!("Retrying certificate for {}", z.name_any());
+                    if retry_requested {
+                        patch_status(ctx.kube.clone(), &z, |status| {
+                            status.certificate_retry = retry
+                        })
+                        .await?;
+                    };
+                    return continue_issuance(&ctx, &z).await;
                 }
-                Stage::Deleting => {}
+                Stage::Deleting | Stage::CertificateRotated(_) => {}
             },
             CustomAction::NoOp => return Ok(Action::await_change()),
         }
     } else {
-        println!("Item was none! {:?}\n", g);
+        info!("WebhookHelper {} no longer exists", name);
     };
 
     Ok(Action::requeue(Duration::from_secs(5)))
 }
+/// Run the next issuance step, finishing a renewal once the helper already serves a webhook
+async fn continue_issuance(ctx: &CustomClients, z: &WebhookHelper) -> Result<Action, Error> {
+    let renewal = z.status.as_ref().map(serving).unwrap_or(false);
+    let operation = if renewal {
+        Operation::Update
+    } else {
+        Operation::Create
+    };
+    let mut cert_stage = CertificateStage::new(
+        ctx.kube.clone(),
+        operation,
+        z.clone(),
+        ctx.namespace.clone(),
+    );
+    cert_stage.run().await?;
+    if renewal {
+        return complete_renewal(ctx, z, &cert_stage).await;
+    };
+    Ok(Action::requeue(Duration::from_secs(5)))
+}
+
+/// Roll the backend and CA bundle over to a renewed certificate once issuance finished
+async fn complete_renewal(
+    ctx: &CustomClients,
+    z: &WebhookHelper,
+    cert_stage: &CertificateStage,
+) -> Result<Action, Error> {
+    if cert_stage.pending() {
+        return Ok(Action::requeue(Duration::from_secs(5)));
+    };
+    if let Stage::CreationFailed(_) | Stage::CertificateFailed(_, _) =
+        determine_stage(ctx.kube.clone(), z.clone()).await?
+    {
+        return Ok(Action::await_change());
+    };
+    if !cert_stage.renewed() {
+        warn!(
+            "Issuer returned an unchanged certificate for {}",
+            z.name_any()
+        );
+        record_rotation(ctx, z).await?;
+        return Ok(Action::requeue(Duration::from_secs(3600)));
+    };
+
+    let mut deploy_stage = DeploymentStage::new(
+        ctx.kube.clone(),
+        Operation::Update,
+        z.clone(),
+        cert_stage.get_secret().await.map(|s| s.name_any()),
+    );
+    deploy_stage.rollout().await?;
+
+    let mut webhook_stage = WebhookStage::new(ctx.kube.clone(), Operation::Update, z.clone(), None);
+    webhook_stage.refresh_ca_bundle().await?;
+    patch_status(ctx.kube.clone(), z, clear_creation_failure).await?;
+    if let Some(webhook) = webhook_stage.get_webhook().await {
+        update_status(ctx.kube.clone(), Stage::WebhookCreated(webhook), z.clone()).await?;
+    };
+    record_rotation(ctx, z).await?;
+    // a deleted pod is started again by the next reconcile
+    if deploy_stage.pending() {
+        return Ok(Action::requeue(Duration::from_secs(5)));
+    };
+    Ok(Action::requeue(Duration::from_secs(10)))
+}
+
+/// Patch the backend, service and webhook configuration to match the current spec, recreating any that were deleted
+///
+/// Returns whether the pod is still being recreated.
+async fn reconcile_children(ctx: &CustomClients, z: &WebhookHelper) -> Result<bool, Error> {
+    let secret = z.status.as_ref().and_then(|s| s.certificate.clone());
+    let mut deploy_stage =
+        DeploymentStage::new(ctx.kube.clone(), Operation::Update, z.clone(), secret);
+    deploy_stage.run().await?;
+
+    let mut service_stage = ServiceStage::new(ctx.kube.clone(), Operation::Update, z.clone(), None);
+    service_stage.run().await?;
+
+    let mut webhook_stage = WebhookStage::new(ctx.kube.clone(), Operation::Update, z.clone(), None);
+    webhook_stage.run().await?;
+    if deploy_stage.pending() {
+        return Ok(true);
+    };
+
+    let observed_generation = z.status.as_ref().and_then(|s| s.observed_generation);
+    if z.meta().generation != observed_generation {
+        patch_status(ctx.kube.clone(), z, |status| {
+            status.observed_generation = z.meta().generation
+        })
+        .await?;
+    };
+    Ok(false)
+}
+
+/// Value of the rotate annotation when it has not been acted on yet
+fn requested_rotation(z: &WebhookHelper) -> Option<String> {
+    let rotation = z.annotations().get(ROTATE_ANNOTATION).cloned()?;
+    let last_rotation = z.status.as_ref().and_then(|s| s.last_rotation.clone());
+    if last_rotation == Some(rotation.clone()) {
+        return None;
+    };
+    Some(rotation)
+}
+
+/// Mark a requested rotation as done so the annotation is only acted on once
+async fn record_rotation(ctx: &CustomClients, z: &WebhookHelper) -> Result<(), Error> {
+    if let Some(rotation) = requested_rotation(z) {
+        update_status(
+            ctx.kube.clone(),
+            Stage::CertificateRotated(rotation),
+            z.clone(),
+        )
+        .await?;
+    };
+    Ok(())
+}
+
 /// an error handler that will be called when the reconciler fails with access to both the
 /// object that caused the failure and the actual error
-fn error_policy(obj: Arc<WebhookHelper>, error: &Error, _ctx: Arc<CustomClients>) -> Action {
-    let def_str = serde_json::to_string(&obj.spec).unwrap();
-    println!("{} received error {:?}", def_str, error);
-    Action::requeue(Duration::from_secs(60))
+fn error_policy(obj: Arc<WebhookHelper>, error: &Error, ctx: Arc<CustomClients>) -> Action {
+    let delay = ctx
+        .backoff
+        .lock()
+        .unwrap()
+        .remove(&obj.name_any())
+        .unwrap_or(BACKOFF_BASE);
+    warn!(
+        "Reconcile of {} failed, retrying in {}s: {}",
+        obj.name_any(),
+        delay.as_secs(),
+        error
+    );
+    Action::requeue(delay)
+}
+
+/// Delay before retrying after `failures` failed reconciles in a row
+///
+/// Half of it is random so helpers failing together do not retry together.
+fn backoff(failures: u32) -> Duration {
+    let delay = BACKOFF_BASE
+        .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+        .min(BACKOFF_MAX);
+    delay / 2 + (delay / 2).mul_f64(rand::random::<f64>())
+}
+
+fn parse_time(timestamp: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .ok()
+        .map(|t| t.with_timezone(&Utc))
 }
 
-pub async fn run() -> Result<(), Error> {
-    let client = Client::try_default().await?;
+/// Time left until `at`, `None` once it has passed
+fn time_until(at: DateTime<Utc>) -> Option<Duration> {
+    (at - Utc::now()).to_std().ok().filter(|d| !d.is_zero())
+}
+
+pub async fn run(namespace: String, retry: RetryPolicy) -> Result<(), Error> {
+    let client = crate::client().await?;
     let api: Api<WebhookHelper> = Api::all(client.clone());
 
     let clients = CustomClients {
         kube: client.clone(),
+        namespace,
+        retry,
+        backoff: Mutex::new(HashMap::new()),
     };
 
     let context = Arc::new(clients); // bad empty context - put client in here
 
     let config = Config::default().concurrency(2);
 
-    Controller::new(api.clone(), watcher::Config::default())
-        .owns(api, watcher::Config::default())
+    // children carry the managed-by label and a controller reference to their helper
+    let children =
+        watcher::Config::default().labels(&format!("{}=webhook-helper", MANAGED_BY_LABEL));
+    let controller = Controller::new(api, watcher::Config::default())
+        .owns(Api::<Deployment>::all(client.clone()), children.clone())
+        .owns(Api::<Pod>::all(client.clone()), children.clone())
+        .owns(Api::<Service>::all(client.clone()), children.clone())
+        .owns(Api::<Secret>::all(client.clone()), children.clone())
+        .owns(
+            Api::<ValidatingWebhookConfiguration>::all(client.clone()),
+            children.clone(),
+        )
+        .owns(
+            Api::<MutatingWebhookConfiguration>::all(client.clone()),
+            children,
+        )
         .with_config(config.clone())
-        .shutdown_on_signal()
+        .shutdown_on_signal();
+
+    let store = controller.store();
+    tokio::spawn(async move {
+        if store.wait_until_ready().await.is_ok() {
+            health::set_watcher_synced();
+        };
+    });
+
+    health::set_controller_running(true);
+    controller
         .run(reconcile, error_policy, context.clone())
         .for_each(|res| async move {
             match res {
@@ -294,8 +810,39 @@ pub async fn run() -> Result<(), Error> {
             }
         })
         .await;
+    health::set_controller_running(false);
 
-    println!("Controller terminated");
+    warn!("Controller terminated");
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_from_the_base() {
+        for (failures, full) in [(1, 5), (2, 10), (3, 20), (4, 40)] {
+            let full = Duration::from_secs(full);
+            let delay = backoff(failures);
+            assert!(delay >= full / 2 && delay <= full, "{failures}: {delay:?}");
+        }
+    }
+
+    #[test]
+    fn backoff_is_capped() {
+        for failures in [7, 10, 32, u32::MAX] {
+            let delay = backoff(failures);
+            assert!(
+                delay >= BACKOFF_MAX / 2 && delay <= BACKOFF_MAX,
+                "{failures}: {delay:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn backoff_of_no_failures_is_the_base() {
+        assert!(backoff(0) <= BACKOFF_BASE);
+    }
+}
@@ -9,6 +9,7 @@ use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+#[allow(clippy::large_enum_variant)]
 #[derive(Serialize, Deserialize, Clone)]
 pub enum DeploymentType {
     Pod(Pod),
@@ -25,11 +26,17 @@ pub enum WebhookType {
 pub enum Stage {
     HelperCreated,
     Deleting,
+    CsrCreated(String),
+    CsrApproved(String),
+    CertificateIssued(String),
+    CertificateRequested(String),
     CertificateCreated(String),
     ServiceCreated(Service),
     DeploymentStarted(DeploymentType),
     DeploymentComplete(DeploymentType),
     WebhookCreated(WebhookType),
+    CertificateRotated(String),
+    CertificateFailed(String, String),
     CreationFailed(String),
 }
 
@@ -37,11 +44,17 @@ impl std::fmt::Display for Stage {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let message: String = match self {
             Stage::HelperCreated => "WebhookHelperCreated".into(),
+            Stage::CsrCreated(_) => "CsrCreated".into(),
+            Stage::CsrApproved(_) => "CsrApproved".into(),
+            Stage::CertificateIssued(_) => "CertificateIssued".into(),
+            Stage::CertificateRequested(_) => "CertificateRequested".into(),
             Stage::CertificateCreated(_) => "CertificateCreated".into(),
             Stage::DeploymentComplete(_) => "DeploymentComplete".into(),
             Stage::DeploymentStarted(_) => "DeploymentStarted".into(),
             Stage::ServiceCreated(_) => "ServiceCreated".into(),
             Stage::WebhookCreated(_) => "WebhookCreated".into(),
+            Stage::CertificateRotated(_) => "CertificateRotated".into(),
+            Stage::CertificateFailed(_, _) => "CertificateFailed".into(),
             Stage::CreationFailed(_) => "CreationFailed".into(),
             Stage::Deleting => "Deleting".into(),
         };
@@ -53,6 +66,10 @@ impl Stage {
     pub fn message(&self) -> String {
         match self {
             Stage::HelperCreated => "Webhook Helper Created".to_string(),
+            Stage::CsrCreated(c) => format!("Certificate Signing Request {} Created", c),
+            Stage::CsrApproved(c) => format!("Certificate Signing Request {} Approved", c),
+            Stage::CertificateIssued(c) => format!("Certificate Signing Request {} Issued", c),
+            Stage::CertificateRequested(c) => format!("cert-manager Certificate {} Requested", c),
             Stage::CertificateCreated(c) => format!("Certificate {} Created", c),
             Stage::DeploymentComplete(d) => match d {
                 DeploymentType::Deployment(dep) => {
@@ -69,20 +86,74 @@ impl Stage {
                 WebhookType::Mutating(m) => format!("Webhook {} Created", m.name_any()),
                 WebhookType::Validating(m) => format!("Webhook {} Created", m.name_any()),
             },
+            Stage::CertificateRotated(r) => format!("Certificate rotated as requested at {}", r),
+            Stage::CertificateFailed(r, m) => format!("Certificate was not issued: {}: {}", r, m),
             Stage::CreationFailed(r) => format!("Webhook-helper failed to created webhook: {}", r),
             Stage::Deleting => "Deleting resource".into(),
         }
     }
+
+    /// Summary of the helper's progress shown as `status.phase`
+    pub fn phase(&self) -> &'static str {
+        match self {
+            Stage::HelperCreated => "Pending",
+            Stage::CsrCreated(_)
+            | Stage::CsrApproved(_)
+            | Stage::CertificateIssued(_)
+            | Stage::CertificateRequested(_) => "Issuing",
+            Stage::CertificateCreated(_)
+            | Stage::DeploymentStarted(_)
+            | Stage::DeploymentComplete(_)
+            | Stage::ServiceCreated(_) => "Deploying",
+            Stage::WebhookCreated(_) | Stage::CertificateRotated(_) => "Ready",
+            Stage::CertificateFailed(_, _) | Stage::CreationFailed(_) => "Failed",
+            Stage::Deleting => "Deleting",
+        }
+    }
 }
 
+/// Condition summarising whether the webhook is served
+pub const READY_CONDITION: &str = "Ready";
+pub const CERTIFICATE_READY_CONDITION: &str = "CertificateReady";
+pub const BACKEND_READY_CONDITION: &str = "BackendReady";
+pub const SERVICE_READY_CONDITION: &str = "ServiceReady";
+pub const WEBHOOK_REGISTERED_CONDITION: &str = "WebhookRegistered";
+
+/// Conditions that make up `Ready`, in the order they are reached
+pub const COMPONENT_CONDITIONS: [&str; 4] = [
+    CERTIFICATE_READY_CONDITION,
+    BACKEND_READY_CONDITION,
+    SERVICE_READY_CONDITION,
+    WEBHOOK_REGISTERED_CONDITION,
+];
+
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema, Default)]
 pub struct WebhookHelperCondition {
     #[serde(rename = "type")]
     pub type__: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
     pub message: String,
     pub status: String,
+    /// RFC3339, only moves when `status` changes
     #[serde(rename = "lastTransitionTime")]
     pub last_transition_time: String,
+    #[serde(rename = "observedGeneration", skip_serializing_if = "Option::is_none")]
+    pub observed_generation: Option<i64>,
+}
+
+/// The certificate currently mounted by the helper's backend
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema, Default)]
+pub struct CertificateDetails {
+    pub secret_name: String,
+    pub serial_number: String,
+    pub sha256_fingerprint: String,
+    /// RFC3339
+    pub not_before: String,
+    /// RFC3339
+    pub not_after: String,
+    pub issuer: String,
+    pub subject_alt_names: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema, Default)]
@@ -93,13 +164,133 @@ pub struct WebhookHelperStatus {
     pub pod: Option<String>,
     pub validating_webhook: Option<String>,
     pub mutating_webhook: Option<String>,
+    pub certificate_details: Option<CertificateDetails>,
+    pub certificate_request: Option<String>,
+    pub failed_generation: Option<i64>,
+    pub certificate_retry: Option<String>,
+    pub last_rotation: Option<String>,
+    /// `metadata.generation` the children were last reconciled against
+    pub observed_generation: Option<i64>,
+    /// Field ownership conflict that keeps a child from being applied
+    pub apply_conflict: Option<String>,
+    /// Step of the creation flow the helper is at, see `Stage`
+    pub stage: Option<String>,
+    /// `Pending`, `Issuing`, `Deploying`, `Ready`, `Failed` or `Deleting`
+    pub phase: Option<String>,
+    /// Reconciles that failed in a row, cleared by the next one that succeeds
+    pub failures: Option<u32>,
+    pub last_error: Option<String>,
+    /// `metadata.generation` the last failed reconcile ran against
+    pub last_error_generation: Option<i64>,
+    /// RFC3339, the helper is not reconciled again before
+    pub retry_after: Option<String>,
+    /// RFC3339, when certificate creation last failed
+    pub creation_failed_at: Option<String>,
+    /// Attempts made to create the certificate again after it failed
+    pub creation_retries: Option<u32>,
     pub conditions: Option<Vec<WebhookHelperCondition>>,
 }
 
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema, Default)]
+pub enum KeyAlgorithm {
+    #[default]
+    Ecdsa,
+    Rsa,
+    Ed25519,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema, Default)]
+pub struct CertificateSubject {
+    pub common_name: Option<String>,
+    pub organization: Option<String>,
+    pub organizational_unit: Option<String>,
+    pub country: Option<String>,
+    pub province: Option<String>,
+    pub locality: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema, Default)]
+pub struct CertificateSpec {
+    /// Private key algorithm, defaults to `Ecdsa`
+    pub algorithm: Option<KeyAlgorithm>,
+    /// Key size in bits, 256 or 384 for `Ecdsa` and 2048, 3072 or 4096 for `Rsa`
+    pub key_size: Option<u32>,
+    /// Requested certificate lifetime in seconds, defaults to 86400
+    pub duration_seconds: Option<i64>,
+    /// Seconds before the certificate expires at which it is re-issued, defaults to a third of the lifetime
+    pub renew_before_seconds: Option<i64>,
+    pub subject: Option<CertificateSubject>,
+    /// Cluster DNS domain used for the fully qualified service name, defaults to `cluster.local`
+    pub cluster_domain: Option<String>,
+    /// Additional DNS names or IP addresses added to the certificate
+    pub extra_sans: Option<Vec<String>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema, Default)]
+pub enum IssuerKind {
+    #[default]
+    KubeletCsr,
+    SelfSignedCa,
+    ExistingSecret,
+    CertManager,
+    ManagedCa,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema, Default)]
+pub struct IssuerSpec {
+    pub kind: IssuerKind,
+    /// Secret in `namespace` holding `tls.crt`, `tls.key` and optionally `ca.crt`, used by `ExistingSecret`
+    pub secret_name: Option<String>,
+    /// Name of the cert-manager issuer, used by `CertManager`
+    pub issuer_name: Option<String>,
+    /// `Issuer` or `ClusterIssuer`, defaults to `Issuer`
+    pub issuer_kind: Option<String>,
+    /// Seconds to wait for the signer before giving up, defaults to 300
+    pub timeout_seconds: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema, Default)]
+pub struct CaConfigMapSpec {
+    pub name: String,
+    /// Key holding the PEM encoded CA, defaults to `ca.crt`
+    pub key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema, Default)]
+pub struct TlsSecretSpec {
+    /// Secret in `namespace` mounted in place of a generated one
+    pub name: String,
+    /// Key holding the certificate, defaults to `tls.crt`
+    pub cert_key: Option<String>,
+    /// Key holding the private key, defaults to `tls.key`
+    pub key_key: Option<String>,
+    /// Key holding the CA, defaults to `ca.crt`, ignored when `ca_config_map` is set
+    pub ca_key: Option<String>,
+    /// ConfigMap in `namespace` holding the CA
+    pub ca_config_map: Option<CaConfigMapSpec>,
+}
+
 #[derive(CustomResource, Serialize, Deserialize, Clone, PartialEq, Debug, JsonSchema)]
 #[kube(group = "webhook-helper.io", version = "v1", kind = "WebhookHelper")]
 #[kube(singular = "webhook-helper", plural = "webhook-helpers")]
 #[kube(status = "WebhookHelperStatus")]
+#[kube(printcolumn = r#"{"name":"Phase", "type":"string", "jsonPath":".status.phase"}"#)]
+#[kube(
+    printcolumn = r#"{"name":"Ready", "type":"string", "jsonPath":".status.conditions[?(@.type==\"Ready\")].status"}"#
+)]
+#[kube(
+    printcolumn = r#"{"name":"Certificate", "type":"string", "jsonPath":".status.conditions[?(@.type==\"CertificateReady\")].status"}"#
+)]
+#[kube(
+    printcolumn = r#"{"name":"Backend", "type":"string", "jsonPath":".status.conditions[?(@.type==\"BackendReady\")].status"}"#
+)]
+#[kube(
+    printcolumn = r#"{"name":"Service", "type":"string", "jsonPath":".status.conditions[?(@.type==\"ServiceReady\")].status"}"#
+)]
+#[kube(
+    printcolumn = r#"{"name":"Webhook", "type":"string", "jsonPath":".status.conditions[?(@.type==\"WebhookRegistered\")].status"}"#
+)]
+#[kube(printcolumn = r#"{"name":"Age", "type":"date", "jsonPath":".metadata.creationTimestamp"}"#)]
 pub struct HelperSpec {
     pub namespace: String,
     pub webhook: Value,
@@ -107,5 +298,10 @@ pub struct HelperSpec {
     pub target_port: Option<i32>,
     pub path: Option<String>,
     pub container_name: Option<String>,
+    pub certificate: Option<CertificateSpec>,
+    pub issuer: Option<IssuerSpec>,
+    pub tls_secret: Option<TlsSecretSpec>,
+    /// Take over fields of the children owned by other field managers instead of reporting a conflict
+    pub force_apply: Option<bool>,
     pub deployment: Value,
 }
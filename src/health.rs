@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use warp::http::StatusCode;
+use warp::{Filter, Rejection, Reply};
+
+static TLS_LOADED: AtomicBool = AtomicBool::new(false);
+static LEADER: AtomicBool = AtomicBool::new(false);
+static CONTROLLER_RUNNING: AtomicBool = AtomicBool::new(false);
+static CONTROLLER_STOPPED: AtomicBool = AtomicBool::new(false);
+static WATCHER_SYNCED: AtomicBool = AtomicBool::new(false);
+
+/// The admission server loaded its certificate and is listening
+pub fn set_tls_loaded() {
+    TLS_LOADED.store(true, Ordering::SeqCst);
+}
+
+/// Whether this replica holds the lease and is expected to reconcile
+pub fn set_leader(leader: bool) {
+    LEADER.store(leader, Ordering::SeqCst);
+}
+
+pub fn set_controller_running(running: bool) {
+    CONTROLLER_RUNNING.store(running, Ordering::SeqCst);
+    if !running {
+        CONTROLLER_STOPPED.store(true, Ordering::SeqCst);
+    };
+}
+
+/// The controller's watcher listed every WebhookHelper
+pub fn set_watcher_synced() {
+    WATCHER_SYNCED.store(true, Ordering::SeqCst);
+}
+
+/// Why the replica is not ready, empty once it is
+///
+/// A follower only serves admission so it is ready once TLS is loaded.
+fn not_ready() -> Vec<&'static str> {
+    let mut reasons = Vec::new();
+    if !TLS_LOADED.load(Ordering::SeqCst) {
+        reasons.push("TLS certificate not loaded");
+    };
+    if LEADER.load(Ordering::SeqCst) {
+        if !CONTROLLER_RUNNING.load(Ordering::SeqCst) {
+            reasons.push("controller not running");
+        };
+        if !WATCHER_SYNCED.load(Ordering::SeqCst) {
+            reasons.push("watcher not synced");
+        };
+    };
+    reasons
+}
+
+fn respond(reasons: Vec<&'static str>) -> impl Reply {
+    match reasons.is_empty() {
+        true => warp::reply::with_status("ok".to_string(), StatusCode::OK),
+        false => warp::reply::with_status(reasons.join(", "), StatusCode::SERVICE_UNAVAILABLE),
+    }
+}
+
+/// `/healthz` fails once the controller stream has stopped, `/readyz` until the replica can do its work
+pub fn routes() -> impl Filter<Extract = (impl Reply,), Error = Rejection> + Clone {
+    let healthz = warp::path("healthz").and(warp::get()).map(|| {
+        let mut reasons = Vec::new();
+        if CONTROLLER_STOPPED.load(Ordering::SeqCst) {
+            reasons.push("controller stopped");
+        };
+        respond(reasons)
+    });
+    let readyz = warp::path("readyz")
+        .and(warp::get())
+        .map(|| respond(not_ready()));
+    healthz.or(readyz)
+}
@@ -0,0 +1,168 @@
+use futures::StreamExt;
+use k8s_openapi::api::admissionregistration::v1::{
+    MutatingWebhookConfiguration, ValidatingWebhookConfiguration,
+};
+use k8s_openapi::apiextensions_apiserver::pkg::apis::apiextensions::v1::CustomResourceDefinition;
+use k8s_openapi::{ByteString, ClusterResourceScope};
+use kube::core::ResourceExt;
+use kube::runtime::controller::Action;
+use kube::runtime::reflector::ObjectRef;
+use kube::runtime::{controller::Controller, watcher};
+use kube::{Api, Client, Resource};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, warn};
+
+use crate::controller::Error;
+use crate::crd::WebhookHelper;
+use crate::operator::{ca_bundle, perform_cluster_get, perform_cluster_operation, Operation};
+
+/// Annotation naming the helper whose CA is injected into the annotated object
+pub const INJECT_ANNOTATION: &str = "webhook-helper.io/inject-ca-from";
+/// Injected objects are re-checked at this interval in case they were edited by hand
+const RESYNC_INTERVAL: Duration = Duration::from_secs(300);
+
+/// Objects carrying a `caBundle` webhook-helper can keep in sync
+trait Injectable {
+    /// Set every `caBundle` to `ca`, returns whether anything changed
+    fn inject(&mut self, ca: &ByteString) -> bool;
+}
+
+impl Injectable for ValidatingWebhookConfiguration {
+    fn inject(&mut self, ca: &ByteString) -> bool {
+        let mut changed = false;
+        for w in self.webhooks.iter_mut().flatten() {
+            if w.client_config.ca_bundle.as_ref() != Some(ca) {
+                w.client_config.ca_bundle = Some(ca.clone());
+                changed = true;
+            };
+        }
+        changed
+    }
+}
+
+impl Injectable for MutatingWebhookConfiguration {
+    fn inject(&mut self, ca: &ByteString) -> bool {
+        let mut changed = false;
+        for w in self.webhooks.iter_mut().flatten() {
+            if w.client_config.ca_bundle.as_ref() != Some(ca) {
+                w.client_config.ca_bundle = Some(ca.clone());
+                changed = true;
+            };
+        }
+        changed
+    }
+}
+
+impl Injectable for CustomResourceDefinition {
+    fn inject(&mut self, ca: &ByteString) -> bool {
+        let client_config = match self.spec.conversion.as_mut() {
+            Some(conversion) if conversion.strategy == "Webhook" => conversion
+                .webhook
+                .as_mut()
+                .and_then(|w| w.client_config.as_mut()),
+            _ => None,
+        };
+        match client_config {
+            Some(c) if c.ca_bundle.as_ref() != Some(ca) => {
+                c.ca_bundle = Some(ca.clone());
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+struct InjectorContext {
+    kube: Client,
+}
+
+async fn reconcile<K>(obj: Arc<K>, ctx: Arc<InjectorContext>) -> Result<Action, Error>
+where
+    K: Injectable + Resource<Scope = ClusterResourceScope> + Clone + DeserializeOwned,
+    K: Debug + Serialize,
+    <K as Resource>::DynamicType: Default,
+{
+    let helper_name = match obj.annotations().get(INJECT_ANNOTATION) {
+        Some(h) => h.clone(),
+        None => return Ok(Action::await_change()),
+    };
+
+    let helper: WebhookHelper = match perform_cluster_get(ctx.kube.clone(), &helper_name).await {
+        Ok(h) => h,
+        Err(kube::Error::Api(e)) if e.code == 404 => {
+            warn!(
+                "WebhookHelper {} referenced by {} not found",
+                helper_name,
+                obj.name_any()
+            );
+            return Ok(Action::requeue(RESYNC_INTERVAL));
+        }
+        Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+    };
+
+    let ca = ByteString(ca_bundle(ctx.kube.clone(), &helper).await?.into_bytes());
+    let mut obj = (*obj).clone();
+    if obj.inject(&ca) {
+        perform_cluster_operation(ctx.kube.clone(), Operation::Update, &obj).await?;
+        info!("Injected CA from {} into {}", helper_name, obj.name_any());
+    };
+    Ok(Action::requeue(RESYNC_INTERVAL))
+}
+
+fn error_policy<K>(obj: Arc<K>, error: &Error, _ctx: Arc<InjectorContext>) -> Action
+where
+    K: Resource,
+{
+    warn!("CA injection into {} failed: {:?}", obj.name_any(), error);
+    Action::requeue(Duration::from_secs(60))
+}
+
+/// Keep `caBundle` of every annotated object of kind `K` in sync, re-checking them whenever their helper changes
+async fn inject<K>(client: Client)
+where
+    K: Injectable + Resource<Scope = ClusterResourceScope> + Clone + DeserializeOwned,
+    K: Debug + Serialize + Send + Sync + 'static,
+    <K as Resource>::DynamicType: Default + Clone + Debug + Eq + std::hash::Hash + Unpin,
+{
+    let api: Api<K> = Api::all(client.clone());
+    let helpers: Api<WebhookHelper> = Api::all(client.clone());
+    let context = Arc::new(InjectorContext { kube: client });
+
+    let controller = Controller::new(api, watcher::Config::default());
+    let store = controller.store();
+    controller
+        .watches(helpers, watcher::Config::default(), move |helper| {
+            store
+                .state()
+                .into_iter()
+                .filter(|o| o.annotations().get(INJECT_ANNOTATION) == Some(&helper.name_any()))
+                .map(|o| ObjectRef::from_obj(o.as_ref()))
+                .collect::<Vec<_>>()
+        })
+        .shutdown_on_signal()
+        .run(reconcile, error_policy, context)
+        .for_each(|res| async move {
+            if let Err(e) = res {
+                warn!("CA injection failed: {:?}", e);
+            };
+        })
+        .await;
+}
+
+pub async fn run() -> Result<(), Error> {
+    let client = crate::client().await?;
+
+    futures::join!(
+        inject::<ValidatingWebhookConfiguration>(client.clone()),
+        inject::<MutatingWebhookConfiguration>(client.clone()),
+        inject::<CustomResourceDefinition>(client),
+    );
+
+    warn!("Injector terminated");
+
+    Ok(())
+}
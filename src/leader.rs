@@ -0,0 +1,282 @@
+use chrono::Utc;
+use k8s_openapi::api::coordination::v1::{Lease, LeaseSpec};
+use k8s_openapi::apimachinery::pkg::apis::meta::v1::MicroTime;
+use kube::api::PostParams;
+use kube::core::ObjectMeta;
+use kube::Api;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+use crate::controller::Error;
+use crate::health;
+
+/// The `coordination.k8s.io` Lease whose holder runs the reconcile loops
+pub struct LeaseConfig {
+    pub name: String,
+    pub namespace: String,
+    /// Name of this replica, the pod name
+    pub identity: String,
+    /// How long the lease is valid after its last renewal
+    pub lease_duration: Duration,
+    /// How often the holder renews the lease and followers try to acquire it
+    pub renew_period: Duration,
+}
+
+impl LeaseConfig {
+    /// How long the leader keeps working without a successful renewal, shorter than `lease_duration`
+    /// so it stops before a follower can consider the lease expired
+    fn renew_deadline(&self) -> Duration {
+        self.lease_duration.saturating_sub(self.renew_period)
+    }
+}
+
+/// The holder and renewal last read from the lease, and when this replica first saw them
+///
+/// Expiry is judged from `at` on this replica's clock rather than from `renewTime`, so clock skew
+/// between replicas cannot make a lease look expired early.
+struct Observed {
+    holder: Option<String>,
+    renew_time: Option<MicroTime>,
+    at: Instant,
+}
+
+/// Wait for the lease and run `work` while holding it
+///
+/// Returns `LeaseLost` if the lease cannot be renewed, `work` is dropped so this replica stops reconciling.
+pub async fn run_as_leader<F>(config: LeaseConfig, work: F) -> Result<(), Error>
+where
+    F: Future<Output = Result<(), Error>>,
+{
+    let client = crate::client().await?;
+    let api: Api<Lease> = Api::namespaced(client, &config.namespace);
+    let mut observed = None;
+
+    info!(
+        "Waiting for lease {}/{} as {}",
+        config.namespace, config.name, config.identity
+    );
+    loop {
+        match try_acquire(&api, &config, &mut observed).await {
+            Ok(true) => break,
+            Ok(false) => {}
+            Err(e) => warn!("Unable to acquire lease {}: {}", config.name, e),
+        };
+        tokio::time::sleep(config.renew_period).await;
+    }
+    info!("Acquired lease {} as {}", config.name, config.identity);
+    health::set_leader(true);
+
+    let result = tokio::select! {
+        result = work => result,
+        lost = keep_renewed(&api, &config, &mut observed) => Err(lost),
+    };
+    health::set_leader(false);
+    if !matches!(result, Err(Error::LeaseLost(_))) {
+        release(&api, &config).await;
+    };
+    result
+}
+
+/// Renew the lease every `renew_period` until it is lost or not renewed within the renew deadline
+async fn keep_renewed(
+    api: &Api<Lease>,
+    config: &LeaseConfig,
+    observed: &mut Option<Observed>,
+) -> Error {
+    let mut renewed = Instant::now();
+    loop {
+        let deadline = tokio::time::Instant::from_std(renewed + config.renew_deadline());
+        let renew = async {
+            tokio::time::sleep(config.renew_period).await;
+            let started = Instant::now();
+            (started, try_acquire(api, config, observed).await)
+        };
+        match tokio::time::timeout_at(deadline, renew).await {
+            Ok((started, Ok(true))) => renewed = started,
+            Ok((_, Ok(false))) => {
+                return Error::LeaseLost(format!("{} is held by another replica", config.name))
+            }
+            // keep trying until the renew deadline passes
+            Ok((_, Err(e))) => warn!("Unable to renew lease {}: {}", config.name, e),
+            Err(_) => {
+                return Error::LeaseLost(format!(
+                    "{} not renewed within {}s",
+                    config.name,
+                    config.renew_deadline().as_secs()
+                ))
+            }
+        };
+    }
+}
+
+/// Take the lease if it is free or expired, or renew it if already held, returns whether it is held
+async fn try_acquire(
+    api: &Api<Lease>,
+    config: &LeaseConfig,
+    observed: &mut Option<Observed>,
+) -> Result<bool, Error> {
+    let now = Utc::now();
+    let duration_seconds = config.lease_duration.as_secs() as i32;
+    let mut lease = match api.get(&config.name).await {
+        Ok(lease) => lease,
+        Err(kube::Error::Api(e)) if e.code == 404 => {
+            let lease = Lease {
+                metadata: ObjectMeta {
+                    name: Some(config.name.clone()),
+                    namespace: Some(config.namespace.clone()),
+                    ..Default::default()
+                },
+                spec: Some(LeaseSpec {
+                    holder_identity: Some(config.identity.clone()),
+                    acquire_time: Some(MicroTime(now)),
+                    renew_time: Some(MicroTime(now)),
+                    lease_duration_seconds: Some(duration_seconds),
+                    lease_transitions: Some(0),
+                }),
+            };
+            return match api.create(&PostParams::default(), &lease).await {
+                Ok(_) => {
+                    *observed = Some(Observed {
+                        holder: Some(config.identity.clone()),
+                        renew_time: Some(MicroTime(now)),
+                        at: Instant::now(),
+                    });
+                    Ok(true)
+                }
+                // another replica created it first
+                Err(kube::Error::Api(e)) if e.code == 409 => Ok(false),
+                Err(e) => Err(Error::WebhookHelperCreationFailed(e)),
+            };
+        }
+        Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+    };
+
+    let spec = lease.spec.get_or_insert_with(LeaseSpec::default);
+    let changed = match observed.as_ref() {
+        Some(o) => o.holder != spec.holder_identity || o.renew_time != spec.renew_time,
+        None => true,
+    };
+    if changed {
+        *observed = Some(Observed {
+            holder: spec.holder_identity.clone(),
+            renew_time: spec.renew_time.clone(),
+            at: Instant::now(),
+        });
+    };
+    if spec.holder_identity.as_ref() != Some(&config.identity) {
+        if !expired(spec, observed.as_ref(), duration_seconds) {
+            return Ok(false);
+        };
+        spec.holder_identity = Some(config.identity.clone());
+        spec.acquire_time = Some(MicroTime(now));
+        spec.lease_transitions = Some(spec.lease_transitions.unwrap_or(0) + 1);
+    };
+    spec.renew_time = Some(MicroTime(now));
+    spec.lease_duration_seconds = Some(duration_seconds);
+
+    // the resourceVersion makes the replace fail if another replica got there first
+    match api
+        .replace(&config.name, &PostParams::default(), &lease)
+        .await
+    {
+        Ok(_) => {
+            *observed = Some(Observed {
+                holder: Some(config.identity.clone()),
+                renew_time: Some(MicroTime(now)),
+                at: Instant::now(),
+            });
+            Ok(true)
+        }
+        Err(kube::Error::Api(e)) if e.code == 409 => Ok(false),
+        Err(e) => Err(Error::WebhookHelperCreationFailed(e)),
+    }
+}
+
+/// Whether the lease held by another replica went unrenewed for its duration since this replica
+/// saw it change, a lease without a holder is free
+fn expired(spec: &LeaseSpec, observed: Option<&Observed>, duration_seconds: i32) -> bool {
+    match (&spec.holder_identity, observed) {
+        (Some(_), Some(o)) => {
+            let valid_for = spec.lease_duration_seconds.unwrap_or(duration_seconds);
+            o.at.elapsed() > Duration::from_secs(valid_for.max(0) as u64)
+        }
+        _ => true,
+    }
+}
+
+/// Give up the lease on shutdown so a follower takes over without waiting for it to expire
+async fn release(api: &Api<Lease>, config: &LeaseConfig) {
+    let mut lease = match api.get(&config.name).await {
+        Ok(lease) => lease,
+        Err(e) => {
+            warn!("Unable to release lease {}: {}", config.name, e);
+            return;
+        }
+    };
+    if let Some(spec) = lease.spec.as_mut() {
+        if spec.holder_identity.as_ref() != Some(&config.identity) {
+            return;
+        };
+        spec.holder_identity = None;
+    };
+    if let Err(e) = api
+        .replace(&config.name, &PostParams::default(), &lease)
+        .await
+    {
+        warn!("Unable to release lease {}: {}", config.name, e);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn held(lease_duration_seconds: Option<i32>) -> LeaseSpec {
+        LeaseSpec {
+            holder_identity: Some("other".into()),
+            lease_duration_seconds,
+            ..Default::default()
+        }
+    }
+
+    fn seen(ago: Duration) -> Observed {
+        Observed {
+            holder: Some("other".into()),
+            renew_time: None,
+            at: Instant::now() - ago,
+        }
+    }
+
+    #[test]
+    fn lease_expires_after_its_duration_on_this_clock() {
+        let spec = held(Some(15));
+        assert!(!expired(&spec, Some(&seen(Duration::from_secs(5))), 15));
+        assert!(expired(&spec, Some(&seen(Duration::from_secs(20))), 15));
+    }
+
+    #[test]
+    fn lease_duration_falls_back_to_the_configured_one() {
+        let spec = held(None);
+        assert!(!expired(&spec, Some(&seen(Duration::from_secs(20))), 30));
+        assert!(expired(&spec, Some(&seen(Duration::from_secs(20))), 15));
+    }
+
+    #[test]
+    fn lease_without_a_holder_is_free() {
+        let spec = LeaseSpec::default();
+        assert!(expired(&spec, Some(&seen(Duration::ZERO)), 15));
+    }
+
+    #[test]
+    fn renew_deadline_leaves_room_before_expiry() {
+        let config = LeaseConfig {
+            name: "webhook-helper".into(),
+            namespace: "webhook-helper".into(),
+            identity: "pod".into(),
+            lease_duration: Duration::from_secs(15),
+            renew_period: Duration::from_secs(5),
+        };
+        assert_eq!(config.renew_deadline(), Duration::from_secs(10));
+    }
+}
@@ -1,10 +1,20 @@
 use clap::{Args, Parser};
-use futures::join;
+use futures::future::try_join3;
+use futures::{try_join, TryFutureExt};
+use http::{Response, StatusCode};
+use kube::client::{ClientBuilder, DynBody};
+use kube::{Client, Config};
+use std::time::Duration;
+use tower::util::MapResponseLayer;
 
 mod admission;
 mod bootstrap;
 mod controller;
 mod crd;
+mod health;
+mod injector;
+mod leader;
+mod metrics;
 mod operator;
 
 #[derive(Parser)]
@@ -20,6 +30,21 @@ enum WebHookHelperCli {
 struct BootstrapArgs {
     #[arg(short, long)]
     namespace: String,
+    /// Replicas of the operator, all serve admission and one reconciles
+    #[arg(long, default_value_t = 2)]
+    replicas: i32,
+    #[command(flatten)]
+    lease: LeaseArgs,
+}
+
+#[derive(Args, Clone)]
+pub struct LeaseArgs {
+    /// Seconds the leader's lease is valid after its last renewal
+    #[arg(long, default_value_t = 15)]
+    lease_duration: u64,
+    /// Seconds between renewals of the lease, must be less than half of `lease_duration`
+    #[arg(long, default_value_t = 5)]
+    lease_renew_period: u64,
 }
 
 #[derive(Args)]
@@ -27,6 +52,49 @@ struct BootstrapArgs {
 pub struct RunArgs {
     #[arg(short, long)]
     port: u16,
+    #[arg(short, long, default_value = "webhook-helper")]
+    namespace: String,
+    /// PEM file with the CA that must sign admission client certificates
+    #[arg(long, conflicts_with = "client_ca_config_map")]
+    client_ca_file: Option<String>,
+    /// ConfigMap in `namespace` with the CA that must sign admission client certificates
+    #[arg(long)]
+    client_ca_config_map: Option<String>,
+    /// Key of the CA in `client_ca_config_map`
+    #[arg(long, default_value = "ca.crt")]
+    client_ca_key: String,
+    /// Port `/metrics`, `/healthz` and `/readyz` are served on over plain HTTP
+    #[arg(long, default_value_t = 8080)]
+    metrics_port: u16,
+    /// Attempts at creating a certificate again after it failed, before giving up until the spec changes
+    #[arg(long, default_value_t = 5)]
+    max_creation_retries: u32,
+    /// Seconds to wait after a failed certificate creation before attempting it again
+    #[arg(long, default_value_t = 300)]
+    creation_retry_cooldown: u64,
+    /// Lease in `namespace` held by the replica that reconciles
+    #[arg(long, default_value = "webhook-helper")]
+    lease_name: String,
+    #[command(flatten)]
+    lease: LeaseArgs,
+}
+
+/// A client for the inferred config that counts every failed API request by status code
+///
+/// A 404 is how a missing object is looked up, e.g. before every apply, so it is not counted.
+pub async fn client() -> Result<Client, controller::Error> {
+    let config = Config::infer().await.map_err(kube::Error::InferConfig)?;
+    let count_errors = MapResponseLayer::new(|response: Response<Box<DynBody>>| {
+        let status = response.status();
+        let failed = status.is_client_error() && status != StatusCode::NOT_FOUND;
+        if failed || status.is_server_error() {
+            metrics::api_error(status.as_str());
+        };
+        response
+    });
+    Ok(ClientBuilder::try_from(config)?
+        .with_layer(&count_errors)
+        .build())
 }
 
 /// something to drive the controller
@@ -34,13 +102,57 @@ pub struct RunArgs {
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing_subscriber::fmt::init();
     match WebHookHelperCli::parse() {
-        WebHookHelperCli::Bootstrap(args) => bootstrap::bootstrap(args.namespace).await?,
+        WebHookHelperCli::Bootstrap(args) => {
+            bootstrap::bootstrap(
+                args.namespace,
+                args.replicas,
+                args.lease.lease_duration,
+                args.lease.lease_renew_period,
+            )
+            .await?
+        }
         WebHookHelperCli::Run(args) => {
-            let adm_proc = admission::serve(args.port);
-            let controller_proc = controller::run();
-            let (adm_result, controller_result) = join!(adm_proc, controller_proc);
-            adm_result?;
-            controller_result?;
+            // the leader stops after `lease_duration - lease_renew_period` without a renewal,
+            // which has to leave room for at least one more renewal attempt
+            if args.lease.lease_renew_period * 2 >= args.lease.lease_duration {
+                return Err(
+                    "--lease-renew-period must be less than half of --lease-duration".into(),
+                );
+            };
+            let client_ca = match (args.client_ca_file, args.client_ca_config_map) {
+                (Some(path), _) => Some(admission::ClientCa::File(path)),
+                (None, Some(name)) => Some(admission::ClientCa::ConfigMap {
+                    namespace: args.namespace.clone(),
+                    name,
+                    key: args.client_ca_key,
+                }),
+                (None, None) => None,
+            };
+            let lease = leader::LeaseConfig {
+                name: args.lease_name,
+                namespace: args.namespace.clone(),
+                // set from the downward API, the hostname is the pod name as well
+                identity: std::env::var("POD_NAME")
+                    .or_else(|_| std::env::var("HOSTNAME"))
+                    .unwrap_or("webhook-helper".into()),
+                lease_duration: Duration::from_secs(args.lease.lease_duration),
+                renew_period: Duration::from_secs(args.lease.lease_renew_period),
+            };
+            // every replica serves admission, only the leader reconciles
+            let adm_proc = admission::serve(args.port, client_ca);
+            let retry = controller::RetryPolicy {
+                max_creation_retries: args.max_creation_retries,
+                creation_retry_cooldown: Duration::from_secs(args.creation_retry_cooldown),
+            };
+            let controller_proc = controller::run(args.namespace.clone(), retry);
+            let injector_proc = injector::run();
+            let renewal_proc = bootstrap::renew_certificate(args.namespace.clone());
+            let leader_proc = leader::run_as_leader(
+                lease,
+                try_join3(controller_proc, injector_proc, renewal_proc).map_ok(|_| ()),
+            );
+            // a replica that lost the lease exits and rejoins as a follower
+            try_join!(adm_proc, leader_proc, metrics::serve(args.metrics_port))?;
         }
     };
 
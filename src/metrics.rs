@@ -0,0 +1,197 @@
+use chrono::DateTime;
+use kube::core::ResourceExt;
+use prometheus::core::Collector;
+use prometheus::{
+    Encoder, GaugeVec, Histogram, HistogramOpts, HistogramVec, IntCounterVec, IntGaugeVec, Opts,
+    Registry, TextEncoder,
+};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+use tracing::{info, warn};
+use warp::Filter;
+
+use crate::controller::Error;
+use crate::crd::WebhookHelper;
+use crate::health;
+
+struct Metrics {
+    registry: Registry,
+    reconciles: IntCounterVec,
+    reconcile_duration: HistogramVec,
+    stage: IntGaugeVec,
+    certificate_not_after: GaugeVec,
+    csr_wait: Histogram,
+    admission_requests: IntCounterVec,
+    api_errors: IntCounterVec,
+    /// Stage last reported per helper, so the series can be dropped when it moves on
+    stages: Mutex<HashMap<String, String>>,
+}
+
+impl Metrics {
+    fn new() -> Metrics {
+        let metrics = Metrics {
+            registry: Registry::new(),
+            reconciles: IntCounterVec::new(
+                Opts::new(
+                    "webhook_helper_reconciles_total",
+                    "Reconciles by action and outcome",
+                ),
+                &["action", "outcome"],
+            )
+            .unwrap(),
+            reconcile_duration: HistogramVec::new(
+                HistogramOpts::new(
+                    "webhook_helper_reconcile_duration_seconds",
+                    "Time taken by a reconcile by action and outcome",
+                )
+                .buckets(vec![
+                    0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0,
+                ]),
+                &["action", "outcome"],
+            )
+            .unwrap(),
+            stage: IntGaugeVec::new(
+                Opts::new(
+                    "webhook_helper_stage",
+                    "Stage each helper is at, 1 for the current stage",
+                ),
+                &["helper", "stage"],
+            )
+            .unwrap(),
+            certificate_not_after: GaugeVec::new(
+                Opts::new(
+                    "webhook_helper_certificate_not_after_timestamp_seconds",
+                    "Expiry of the certificate mounted by each helper",
+                ),
+                &["helper"],
+            )
+            .unwrap(),
+            csr_wait: Histogram::with_opts(
+                HistogramOpts::new(
+                    "webhook_helper_csr_wait_duration_seconds",
+                    "Time from creating a CertificateSigningRequest until it was issued",
+                )
+                .buckets(vec![
+                    1.0, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0,
+                ]),
+            )
+            .unwrap(),
+            admission_requests: IntCounterVec::new(
+                Opts::new(
+                    "webhook_helper_admission_requests_total",
+                    "WebhookHelper admission reviews by result and reason",
+                ),
+                &["result", "reason"],
+            )
+            .unwrap(),
+            api_errors: IntCounterVec::new(
+                Opts::new(
+                    "webhook_helper_kube_api_errors_total",
+                    "Failed Kubernetes API requests by status code, not counting 404",
+                ),
+                &["code"],
+            )
+            .unwrap(),
+            stages: Mutex::new(HashMap::new()),
+        };
+
+        let collectors: Vec<Box<dyn Collector>> = vec![
+            Box::new(metrics.reconciles.clone()),
+            Box::new(metrics.reconcile_duration.clone()),
+            Box::new(metrics.stage.clone()),
+            Box::new(metrics.certificate_not_after.clone()),
+            Box::new(metrics.csr_wait.clone()),
+            Box::new(metrics.admission_requests.clone()),
+            Box::new(metrics.api_errors.clone()),
+        ];
+        for collector in collectors {
+            metrics.registry.register(collector).unwrap();
+        }
+        metrics
+    }
+
+    /// Drop the stage series of the helper `name`
+    fn forget_stage(&self, name: &str) {
+        if let Some(stage) = self.stages.lock().unwrap().remove(name) {
+            let _ = self.stage.remove_label_values(&[name, &stage]);
+        };
+    }
+}
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+/// Count a reconcile of `action`, `outcome` is `success` or `error`
+pub fn reconciled(action: &str, outcome: &str, duration: Duration) {
+    let labels = [action, outcome];
+    METRICS.reconciles.with_label_values(&labels).inc();
+    METRICS
+        .reconcile_duration
+        .with_label_values(&labels)
+        .observe(duration.as_secs_f64());
+}
+
+/// Record the stage and certificate expiry of a helper from its status
+pub fn observe_helper(helper: &WebhookHelper) {
+    let name = helper.name_any();
+    let status = match helper.status.as_ref() {
+        Some(status) => status,
+        None => return,
+    };
+    if let Some(stage) = status.stage.clone() {
+        METRICS.forget_stage(&name);
+        METRICS.stage.with_label_values(&[&name, &stage]).set(1);
+        METRICS.stages.lock().unwrap().insert(name.clone(), stage);
+    };
+    if let Some(details) = status.certificate_details.as_ref() {
+        if let Ok(not_after) = DateTime::parse_from_rfc3339(&details.not_after) {
+            METRICS
+                .certificate_not_after
+                .with_label_values(&[&name])
+                .set(not_after.timestamp() as f64);
+        };
+    };
+}
+
+/// Drop the series of a deleted helper
+pub fn forget_helper(name: &str) {
+    METRICS.forget_stage(name);
+    let _ = METRICS.certificate_not_after.remove_label_values(&[name]);
+}
+
+pub fn csr_issued(wait: Duration) {
+    METRICS.csr_wait.observe(wait.as_secs_f64());
+}
+
+pub fn admission_request(allowed: bool, reason: &str) {
+    let result = if allowed { "allowed" } else { "denied" };
+    METRICS
+        .admission_requests
+        .with_label_values(&[result, reason])
+        .inc();
+}
+
+/// Count a failed Kubernetes API request by its status code
+pub fn api_error(code: &str) {
+    METRICS.api_errors.with_label_values(&[code]).inc();
+}
+
+/// Every metric in the Prometheus text format
+pub fn render() -> String {
+    let mut out = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&METRICS.registry.gather(), &mut out) {
+        warn!("Unable to encode metrics: {}", e);
+    };
+    String::from_utf8(out).unwrap_or_default()
+}
+
+/// Serve `/metrics` along with the probes over plain HTTP
+pub async fn serve(port: u16) -> Result<(), Error> {
+    let metrics = warp::path("metrics")
+        .and(warp::get())
+        .map(|| warp::reply::with_header(render(), "content-type", prometheus::TEXT_FORMAT));
+    let routes = metrics.or(health::routes());
+    info!("Serving metrics and probes on port {}", port);
+    warp::serve(routes).run(([0, 0, 0, 0], port)).await;
+    Ok(())
+}
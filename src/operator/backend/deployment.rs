@@ -1,17 +1,46 @@
+use chrono::Utc;
+use k8s_openapi::api::apps::v1::Deployment;
+use k8s_openapi::api::core::v1::Pod;
+use kube::api::{Patch, PatchParams};
 use kube::core::ObjectMeta;
 use kube::core::ResourceExt;
+use kube::Api;
+use serde_json::{json, Value};
 use std::collections::BTreeMap;
 use tracing::{error, info};
 
 use super::perform_operation;
 use super::DeploymentStage;
-use super::Operation;
+use super::{apply_operation, set_owner, spec_changed, stamp_spec_hash};
 use super::{convert_to_deployment, convert_to_pod, validate_container_name};
+use super::{Operation, FIELD_MANAGER};
 use crate::controller::Error;
 use crate::crd::DeploymentType;
 
 impl DeploymentStage {
     pub async fn create_deployment(&mut self) -> Result<DeploymentType, Error> {
+        let deployment_type = match self.desired_deployment().await? {
+            DeploymentType::Deployment(d) => {
+                let result =
+                    perform_operation(self.client.clone(), apply_operation(&self.webhook), &d)
+                        .await?;
+                info!("Deployment {} started", d.name_any());
+                DeploymentType::Deployment(result)
+            }
+            DeploymentType::Pod(p) => {
+                let result =
+                    perform_operation(self.client.clone(), apply_operation(&self.webhook), &p)
+                        .await?;
+                info!("Deployment {} started", p.name_any());
+                DeploymentType::Pod(result)
+            }
+        };
+        self.deployment = Some(deployment_type.clone());
+        Ok(deployment_type)
+    }
+
+    /// The Deployment or Pod as the spec currently describes it
+    async fn desired_deployment(&self) -> Result<DeploymentType, Error> {
         if let Ok(mut d) = convert_to_deployment(self.webhook.spec.deployment.clone()).await {
             d.metadata.namespace = Some(self.webhook.spec.namespace.clone());
 
@@ -66,11 +95,9 @@ impl DeploymentStage {
                 }
             };
 
-            let result = perform_operation(self.client.clone(), Operation::Create, &d).await?;
-            let deployment_type = DeploymentType::Deployment(result);
-            self.deployment = Some(deployment_type.clone());
-            info!("Deployment {} started", d.name_any());
-            Ok(deployment_type)
+            set_owner(&mut d, &self.webhook);
+            stamp_spec_hash(&mut d)?;
+            Ok(DeploymentType::Deployment(d))
         } else if let Ok(mut p) = convert_to_pod(self.webhook.spec.deployment.clone()).await {
             p.metadata.namespace = Some(self.webhook.spec.namespace.clone());
             self.add_labels(&mut p).await;
@@ -83,19 +110,82 @@ impl DeploymentStage {
                 p.spec = Some(pod_spec);
             };
 
-            let result = perform_operation(self.client.clone(), Operation::Create, &p).await?;
-            let deployment_type = DeploymentType::Pod(result);
-            self.deployment = Some(deployment_type.clone());
-            info!("Deployment {} started", p.name_any());
-            Ok(deployment_type)
+            set_owner(&mut p, &self.webhook);
+            stamp_spec_hash(&mut p)?;
+            Ok(DeploymentType::Pod(p))
         } else {
             error!("Unable to determine kind of deployment object");
-            return Err(Error::UnableToCreateObject(
+            Err(Error::UnableToCreateObject(
                 "Invalid deployment spec".into(),
-            ));
+            ))
         }
     }
 
+    /// Bring the live backend in line with the spec, recreating it if it was deleted
+    ///
+    /// Pods are deleted and recreated on a later reconcile as their spec is immutable, a Deployment
+    /// scaled to zero is scaled back up.
+    pub async fn update_deployment(&mut self) -> Result<(), Error> {
+        self.load_deployment().await?;
+        let live = match self.deployment.clone() {
+            Some(live) => live,
+            None => {
+                let deployment = self.create_deployment().await?;
+                match deployment {
+                    DeploymentType::Deployment(d) => info!("Deployment {} recreated", d.name_any()),
+                    DeploymentType::Pod(p) => info!("Pod {} recreated", p.name_any()),
+                };
+                return Ok(());
+            }
+        };
+
+        if let DeploymentType::Pod(p) = &live {
+            // still terminating after an earlier recreate
+            if p.metadata.deletion_timestamp.is_some() {
+                self.pending = true;
+                return Ok(());
+            };
+        };
+
+        match (self.desired_deployment().await?, live) {
+            (DeploymentType::Deployment(desired), DeploymentType::Deployment(live))
+                if desired.name_any() == live.name_any() =>
+            {
+                let replicas = desired.spec.as_ref().and_then(|s| s.replicas);
+                let scaled_down =
+                    live.spec.as_ref().and_then(|s| s.replicas) == Some(0) && replicas != Some(0);
+                if scaled_down {
+                    self.scale(&live, replicas.unwrap_or(1)).await?;
+                };
+                if !spec_changed(&desired, &live) {
+                    return Ok(());
+                };
+                let result = perform_operation(
+                    self.client.clone(),
+                    apply_operation(&self.webhook),
+                    &desired,
+                )
+                .await?;
+                self.deployment = Some(DeploymentType::Deployment(result));
+                info!("Deployment {} updated", desired.name_any());
+            }
+            (DeploymentType::Pod(desired), DeploymentType::Pod(live))
+                if desired.name_any() == live.name_any() =>
+            {
+                if !spec_changed(&desired, &live) {
+                    return Ok(());
+                };
+                self.recreate_pod(&live).await?;
+            }
+            _ => {
+                return Err(Error::UnableToCreateObject(
+                    "The kind and name of the deployment cannot be changed".into(),
+                ))
+            }
+        };
+        Ok(())
+    }
+
     pub async fn get_deployment_status(&self) -> Result<DeploymentType, Error> {
         if let Some(deployment) = self.deployment.clone() {
             match deployment {
@@ -138,6 +228,73 @@ impl DeploymentStage {
         Err(Error::ResourceNotReady)
     }
 
+    /// Set the replicas through the scale subresource
+    ///
+    /// `kubectl scale` leaves `spec.replicas` owned by another field manager, so applying it
+    /// without force would conflict.
+    async fn scale(&mut self, deployment: &Deployment, replicas: i32) -> Result<(), Error> {
+        let api: Api<Deployment> =
+            Api::namespaced(self.client.clone(), &self.webhook.spec.namespace);
+        let scale: Value = json!({
+            "spec": {
+                "replicas": replicas
+            }
+        });
+        let params = PatchParams {
+            field_manager: Some(FIELD_MANAGER.into()),
+            ..Default::default()
+        };
+        api.patch_scale(&deployment.name_any(), &params, &Patch::Merge(&scale))
+            .await?;
+        info!(
+            "Deployment {} scaled back to {} replicas",
+            deployment.name_any(),
+            replicas
+        );
+        Ok(())
+    }
+
+    /// Restart the backend so it picks up a re-issued certificate
+    pub async fn rollout(&mut self) -> Result<(), Error> {
+        self.load_deployment().await?;
+        match self.deployment.clone() {
+            Some(DeploymentType::Deployment(dep)) => {
+                let api: Api<Deployment> =
+                    Api::namespaced(self.client.clone(), &self.webhook.spec.namespace);
+                let restart: Value = json!({
+                    "spec": {
+                        "template": {
+                            "metadata": {
+                                "annotations": {
+                                    "webhook-helper.io/restartedAt": Utc::now().to_rfc3339()
+                                }
+                            }
+                        }
+                    }
+                });
+                // applying only the annotation under FIELD_MANAGER would give up the rest of the spec
+                let params = PatchParams {
+                    field_manager: Some(FIELD_MANAGER.into()),
+                    ..Default::default()
+                };
+                let patch: Patch<&Value> = Patch::Merge(&restart);
+                api.patch(&dep.name_any(), &params, &patch).await?;
+                info!("Deployment {} restarted", dep.name_any());
+            }
+            Some(DeploymentType::Pod(pod)) => self.recreate_pod(&pod).await?,
+            None => {}
+        };
+        Ok(())
+    }
+
+    /// Delete the pod, a later reconcile starts it again from the spec once it is gone
+    async fn recreate_pod(&mut self, pod: &Pod) -> Result<(), Error> {
+        perform_operation(self.client.clone(), Operation::Delete, pod).await?;
+        self.pending = true;
+        info!("Pod {} deleted to be recreated", pod.name_any());
+        Ok(())
+    }
+
     pub async fn get_deployment(&self) -> Option<DeploymentType> {
         self.deployment.clone()
     }
@@ -1,10 +1,12 @@
 use k8s_openapi::api::apps::v1::Deployment;
 use k8s_openapi::api::core::v1::Pod;
-use kube::core::ResourceExt;
 use kube::Client;
 
 use super::perform_operation;
-use super::{determine_stage, perform_get, update_status, Operation};
+use super::{
+    apply_operation, determine_stage, perform_get, set_owner, spec_changed, stamp_spec_hash,
+    update_status, Operation, FIELD_MANAGER,
+};
 use crate::controller::Error;
 use crate::crd::{DeploymentType, Stage, WebhookHelper};
 
@@ -19,6 +21,7 @@ pub struct DeploymentStage {
     webhook: WebhookHelper,
     secret: Option<String>,
     deployment: Option<DeploymentType>,
+    pending: bool,
 }
 
 impl DeploymentStage {
@@ -34,9 +37,15 @@ impl DeploymentStage {
             webhook,
             deployment: None,
             secret,
+            pending: false,
         }
     }
 
+    /// Whether a pod is being deleted and the helper should be requeued to start it again
+    pub fn pending(&self) -> bool {
+        self.pending
+    }
+
     pub async fn run(&mut self) -> Result<(), Error> {
         match self.operation {
             Operation::Bootstrap => {
@@ -59,22 +68,8 @@ impl DeploymentStage {
                 return Ok(());
             }
             Operation::Delete => {
-                if let Some(status) = self.webhook.status.clone() {
-                    if let Some(dep) = status.deployment {
-                        let deployment: Deployment =
-                            perform_get(self.client.clone(), &dep, &self.webhook.spec.namespace)
-                                .await?;
-                        self.deployment = Some(DeploymentType::Deployment(deployment));
-                    } else if let Some(p) = status.pod {
-                        let pod: Pod =
-                            perform_get(self.client.clone(), &p, &self.webhook.spec.namespace)
-                                .await?;
-                        self.deployment = Some(DeploymentType::Pod(pod));
-                    } else {
-                        return Ok(());
-                    };
-                    self.delete().await?;
-                };
+                self.load_deployment().await?;
+                self.delete().await?;
                 return Ok(());
             }
             _ => {
@@ -88,26 +83,6 @@ impl DeploymentStage {
                             self.webhook.clone(),
                         )
                         .await?;
-                        if let Some(uid) = self.webhook.uid() {
-                            match deployment {
-                                DeploymentType::Deployment(d) => {
-                                    perform_operation(
-                                        self.client.clone(),
-                                        Operation::ApplyOwner(uid),
-                                        &d,
-                                    )
-                                    .await?;
-                                }
-                                DeploymentType::Pod(p) => {
-                                    perform_operation(
-                                        self.client.clone(),
-                                        Operation::ApplyOwner(uid),
-                                        &p,
-                                    )
-                                    .await?;
-                                }
-                            };
-                        };
                     }
                     Stage::DeploymentStarted(deployment) => {
                         self.deployment = Some(deployment);
@@ -120,11 +95,37 @@ impl DeploymentStage {
                         .await?;
                     }
                     _ => {
-                        // check if operation is update
+                        if let Operation::Update = self.operation {
+                            self.update_deployment().await?;
+                        };
                     }
                 };
             }
         };
         Ok(())
     }
+
+    /// Load the Deployment or Pod recorded in status, leaving it unset if it no longer exists
+    async fn load_deployment(&mut self) -> Result<(), Error> {
+        if let Some(status) = self.webhook.status.clone() {
+            let namespace = &self.webhook.spec.namespace;
+            let result = if let Some(dep) = status.deployment {
+                perform_get::<Deployment>(self.client.clone(), &dep, namespace)
+                    .await
+                    .map(DeploymentType::Deployment)
+            } else if let Some(p) = status.pod {
+                perform_get::<Pod>(self.client.clone(), &p, namespace)
+                    .await
+                    .map(DeploymentType::Pod)
+            } else {
+                return Ok(());
+            };
+            match result {
+                Ok(deployment) => self.deployment = Some(deployment),
+                Err(kube::Error::Api(e)) if e.code == 404 => self.deployment = None,
+                Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+            };
+        };
+        Ok(())
+    }
 }
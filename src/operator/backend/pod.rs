@@ -1,12 +1,17 @@
 use super::DeploymentStage;
 use crate::controller::Error;
+use crate::crd::TlsSecretSpec;
+use crate::operator::certificate::{DEFAULT_CA_KEY, DEFAULT_CERT_KEY, DEFAULT_KEY_KEY};
+use crate::operator::set_managed_by;
 use k8s_openapi::api::core::v1::Container;
 use k8s_openapi::api::core::v1::PodSpec;
 use k8s_openapi::api::core::v1::SecretVolumeSource;
 use k8s_openapi::api::core::v1::Volume;
 use k8s_openapi::api::core::v1::VolumeMount;
+use k8s_openapi::api::core::v1::{
+    ConfigMapProjection, KeyToPath, ProjectedVolumeSource, SecretProjection, VolumeProjection,
+};
 use kube::core::ResourceExt;
-use std::collections::BTreeMap;
 
 impl DeploymentStage {
     pub async fn add_volume_mount(&self, pod_spec: &mut PodSpec) -> Result<(), Error> {
@@ -47,25 +52,22 @@ impl DeploymentStage {
         pod_spec.containers = containers;
 
         if let Some(secret) = self.secret.clone() {
-            if let Some(mut volumes) = pod_spec.volumes.clone() {
-                volumes.push(Volume {
+            let volume = match self.webhook.spec.tls_secret.clone() {
+                Some(tls_secret) => provided_volume(secret, tls_secret),
+                None => Volume {
                     name: "webhook-helper".into(),
                     secret: Some(SecretVolumeSource {
                         secret_name: Some(secret.clone()),
                         ..Default::default()
                     }),
                     ..Default::default()
-                });
+                },
+            };
+            if let Some(mut volumes) = pod_spec.volumes.clone() {
+                volumes.push(volume);
                 pod_spec.volumes = Some(volumes);
             } else {
-                pod_spec.volumes = Some(vec![Volume {
-                    name: "webhook-helper".into(),
-                    secret: Some(SecretVolumeSource {
-                        secret_name: Some(secret.clone()),
-                        ..Default::default()
-                    }),
-                    ..Default::default()
-                }])
+                pod_spec.volumes = Some(vec![volume])
             }
         };
 
@@ -73,19 +75,49 @@ impl DeploymentStage {
     }
 
     pub async fn add_labels<T: ResourceExt>(&self, resource: &mut T) {
-        if let Some(mut labels) = resource.meta().labels.clone() {
-            labels.insert(
-                "app.kubernetes.io/managed-by".into(),
-                "webhook-helper".into(),
-            );
-            resource.meta_mut().labels = Some(labels);
-        } else {
-            let mut labels: BTreeMap<String, String> = BTreeMap::new();
-            labels.insert(
-                "app.kubernetes.io/managed-by".into(),
-                "webhook-helper".into(),
-            );
-            resource.meta_mut().labels = Some(labels);
-        };
+        set_managed_by(resource);
+    }
+}
+
+/// Project the custom keys of a provided secret, and optionally a CA ConfigMap, onto the standard file names
+fn provided_volume(secret: String, tls_secret: TlsSecretSpec) -> Volume {
+    let item = |key: Option<String>, default: &str| KeyToPath {
+        key: key.unwrap_or(default.into()),
+        path: default.into(),
+        mode: None,
+    };
+
+    let mut secret_items = vec![
+        item(tls_secret.cert_key, DEFAULT_CERT_KEY),
+        item(tls_secret.key_key, DEFAULT_KEY_KEY),
+    ];
+    let mut sources = Vec::new();
+    match tls_secret.ca_config_map {
+        Some(config_map) => sources.push(VolumeProjection {
+            config_map: Some(ConfigMapProjection {
+                name: Some(config_map.name),
+                items: Some(vec![item(config_map.key, DEFAULT_CA_KEY)]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }),
+        None => secret_items.push(item(tls_secret.ca_key, DEFAULT_CA_KEY)),
+    };
+    sources.push(VolumeProjection {
+        secret: Some(SecretProjection {
+            name: Some(secret),
+            items: Some(secret_items),
+            ..Default::default()
+        }),
+        ..Default::default()
+    });
+
+    Volume {
+        name: "webhook-helper".into(),
+        projected: Some(ProjectedVolumeSource {
+            sources: Some(sources),
+            ..Default::default()
+        }),
+        ..Default::default()
     }
 }
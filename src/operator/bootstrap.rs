@@ -10,7 +10,12 @@ use kube::Client;
 use tracing::info;
 
 pub async fn bootstrap(client: Client, webhook: WebhookHelper) -> Result<(), Error> {
-    let mut cert = CertificateStage::new(client.clone(), Operation::Bootstrap, webhook.clone());
+    let mut cert = CertificateStage::new(
+        client.clone(),
+        Operation::Bootstrap,
+        webhook.clone(),
+        webhook.spec.namespace.clone(),
+    );
     cert.run().await?;
 
     let mut deployment = DeploymentStage::new(
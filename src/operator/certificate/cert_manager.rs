@@ -0,0 +1,233 @@
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::Secret;
+use kube::api::{ApiResource, DeleteParams, DynamicObject, GroupVersionKind};
+use kube::core::ResourceExt;
+use kube::{Api, Client};
+use serde_json::{json, Value};
+use tracing::info;
+
+use super::issuer::{dns_names, ip_addresses, read_tls_secret, timeout_seconds, Issuance, Issuer};
+use super::{duration_seconds, renew_before_seconds};
+use crate::controller::Error;
+use crate::crd::{KeyAlgorithm, Stage, WebhookHelper};
+use crate::operator::{perform_get, perform_operation, server_side_apply, set_owner, Operation};
+
+/// When issuance was last requested, the timeout is counted from it
+const REQUESTED_AT_ANNOTATION: &str = "webhook-helper.io/requested-at";
+
+/// Requests the serving certificate from a cert-manager `Issuer` or `ClusterIssuer`
+///
+/// Like the CSR flow, `issue` only applies the `Certificate` and later reconciles pick the
+/// secret up once cert-manager has written it.
+pub struct CertManagerIssuer {
+    client: Client,
+    webhook: WebhookHelper,
+}
+
+impl CertManagerIssuer {
+    pub fn new(client: Client, webhook: WebhookHelper) -> CertManagerIssuer {
+        CertManagerIssuer { client, webhook }
+    }
+
+    fn name(&self) -> String {
+        format!("{}-cert-manager", self.webhook.name_any().to_lowercase())
+    }
+
+    fn api(&self) -> Api<DynamicObject> {
+        let gvk = GroupVersionKind::gvk("cert-manager.io", "v1", "Certificate");
+        Api::namespaced_with(
+            self.client.clone(),
+            &self.webhook.spec.namespace,
+            &ApiResource::from_gvk(&gvk),
+        )
+    }
+
+    fn certificate(&self) -> Result<DynamicObject, Error> {
+        let issuer = self.webhook.spec.issuer.clone().unwrap_or_default();
+        let issuer_name = match issuer.issuer_name {
+            Some(i) => i,
+            None => {
+                return Err(Error::UnableToCreateObject(
+                    "CertManager issuer requires issuer_name".into(),
+                ))
+            }
+        };
+
+        let certificate_spec = self.webhook.spec.certificate.clone().unwrap_or_default();
+        let subject = certificate_spec.subject.unwrap_or_default();
+        let algorithm = match certificate_spec.algorithm.unwrap_or_default() {
+            KeyAlgorithm::Ecdsa => "ECDSA",
+            KeyAlgorithm::Rsa => "RSA",
+            KeyAlgorithm::Ed25519 => "Ed25519",
+        };
+
+        let gvk = GroupVersionKind::gvk("cert-manager.io", "v1", "Certificate");
+        let mut certificate = DynamicObject::new(&self.name(), &ApiResource::from_gvk(&gvk))
+            .within(&self.webhook.spec.namespace)
+            .data(json!({
+                "spec": {
+                    "secretName": self.name(),
+                    "dnsNames": dns_names(&self.webhook)?,
+                    "ipAddresses": ip_addresses(&self.webhook)
+                        .iter()
+                        .map(|ip| ip.to_string())
+                        .collect::<Vec<String>>(),
+                    "commonName": subject.common_name,
+                    "subject": {
+                        "organizations": subject.organization.map(|v| vec![v]),
+                        "organizationalUnits": subject.organizational_unit.map(|v| vec![v]),
+                        "countries": subject.country.map(|v| vec![v]),
+                        "provinces": subject.province.map(|v| vec![v]),
+                        "localities": subject.locality.map(|v| vec![v]),
+                    },
+                    "privateKey": {
+                        "algorithm": algorithm,
+                        "size": certificate_spec.key_size,
+                        "rotationPolicy": "Always",
+                    },
+                    "duration": format!("{}s", duration_seconds(&self.webhook.spec)),
+                    "renewBefore": format!("{}s", renew_before_seconds(&self.webhook.spec)),
+                    "usages": ["digital signature", "key encipherment", "server auth"],
+                    "issuerRef": {
+                        "group": "cert-manager.io",
+                        "kind": issuer.issuer_kind.unwrap_or("Issuer".into()),
+                        "name": issuer_name,
+                    }
+                }
+            }));
+        certificate
+            .annotations_mut()
+            .insert(REQUESTED_AT_ANNOTATION.into(), Utc::now().to_rfc3339());
+        set_owner(&mut certificate, &self.webhook);
+        Ok(certificate)
+    }
+
+    /// The issued secret, or `None` while cert-manager has not written it
+    async fn issued_secret(&self) -> Result<Option<Secret>, Error> {
+        match perform_get::<Secret>(
+            self.client.clone(),
+            &self.name(),
+            &self.webhook.spec.namespace,
+        )
+        .await
+        {
+            Ok(secret) => {
+                let data = secret.data.clone().unwrap_or_default();
+                if data.contains_key("tls.crt") && data.contains_key("tls.key") {
+                    return Ok(Some(secret));
+                };
+            }
+            Err(kube::Error::Api(e)) if e.code == 404 => {}
+            Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+        };
+
+        let requested = match self.api().get(&self.name()).await {
+            Ok(certificate) => certificate
+                .annotations()
+                .get(REQUESTED_AT_ANNOTATION)
+                .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+                .map(|t| t.with_timezone(&Utc)),
+            Err(kube::Error::Api(e)) if e.code == 404 => None,
+            Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+        };
+        let timeout = timeout_seconds(&self.webhook);
+        match requested {
+            Some(requested) if Utc::now() - requested <= chrono::Duration::seconds(timeout) => {
+                Ok(None)
+            }
+            _ => Err(Error::CertificateNotIssued(format!(
+                "Secret {} was not issued within {}s",
+                self.name(),
+                timeout
+            ))),
+        }
+    }
+}
+
+#[async_trait]
+impl Issuer for CertManagerIssuer {
+    async fn issue(&mut self) -> Result<Issuance, Error> {
+        let api = self.api();
+        let existed = match api.get(&self.name()).await {
+            Ok(_) => true,
+            Err(kube::Error::Api(e)) if e.code == 404 => false,
+            Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+        };
+
+        let mut certificate = self.certificate()?;
+        strip_nulls(&mut certificate.data);
+        let force = self.webhook.spec.force_apply.unwrap_or(false);
+        server_side_apply(api, force, &certificate).await?;
+
+        if existed {
+            // cert-manager re-issues as soon as the target secret disappears
+            if let Ok(secret) = perform_get::<Secret>(
+                self.client.clone(),
+                &self.name(),
+                &self.webhook.spec.namespace,
+            )
+            .await
+            {
+                perform_operation(self.client.clone(), Operation::Delete, &secret).await?;
+            };
+        } else {
+            info!("cert-manager Certificate {} created", self.name());
+        };
+
+        Ok(Issuance::Pending(Stage::CertificateRequested(self.name())))
+    }
+
+    async fn resume(&mut self, stage: &Stage) -> Result<Issuance, Error> {
+        match stage {
+            // helpers requested before the stage existed were left at `CsrCreated`
+            Stage::CertificateRequested(_) | Stage::CsrCreated(_) => {
+                match self.issued_secret().await? {
+                    Some(secret) => {
+                        info!("cert-manager Certificate {} issued", self.name());
+                        Ok(Issuance::Issued(read_tls_secret(secret)?))
+                    }
+                    None => Ok(Issuance::Pending(Stage::CertificateRequested(self.name()))),
+                }
+            }
+            _ => Err(Error::UnknownOperation(format!(
+                "Unable to resume certificate issuance from {}",
+                stage
+            ))),
+        }
+    }
+
+    async fn delete(&self) -> Result<(), Error> {
+        match self
+            .api()
+            .delete(&self.name(), &DeleteParams::default())
+            .await
+        {
+            Ok(_) => {}
+            Err(kube::Error::Api(e)) if e.code == 404 => {}
+            Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+        };
+
+        if let Ok(secret) = perform_get::<Secret>(
+            self.client.clone(),
+            &self.name(),
+            &self.webhook.spec.namespace,
+        )
+        .await
+        {
+            perform_operation(self.client.clone(), Operation::Delete, &secret).await?;
+        };
+        Ok(())
+    }
+}
+
+/// Leave unset optional fields out of the applied object, with server-side apply dropping a field
+/// from it is what removes it
+fn strip_nulls(value: &mut Value) {
+    if let Value::Object(map) = value {
+        map.retain(|_, v| !v.is_null());
+        for v in map.values_mut() {
+            strip_nulls(v);
+        }
+    };
+}
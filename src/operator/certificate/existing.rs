@@ -0,0 +1,59 @@
+use async_trait::async_trait;
+use k8s_openapi::api::core::v1::Secret;
+use kube::core::ResourceExt;
+use kube::Client;
+use tracing::info;
+
+use super::issuer::{read_tls_secret, Issuance, Issuer};
+use super::provided::validate_provided;
+use crate::controller::Error;
+use crate::crd::WebhookHelper;
+use crate::operator::perform_get;
+
+/// Copies an already issued certificate out of a user provided secret, checked like `tls_secret`
+pub struct ExistingSecretIssuer {
+    client: Client,
+    webhook: WebhookHelper,
+}
+
+impl ExistingSecretIssuer {
+    pub fn new(client: Client, webhook: WebhookHelper) -> ExistingSecretIssuer {
+        ExistingSecretIssuer { client, webhook }
+    }
+}
+
+#[async_trait]
+impl Issuer for ExistingSecretIssuer {
+    async fn issue(&mut self) -> Result<Issuance, Error> {
+        let secret_name = match self
+            .webhook
+            .spec
+            .issuer
+            .as_ref()
+            .and_then(|i| i.secret_name.clone())
+        {
+            Some(s) => s,
+            None => {
+                return Err(Error::UnableToCreateObject(
+                    "ExistingSecret issuer requires secret_name".into(),
+                ))
+            }
+        };
+
+        let secret: Secret = perform_get(
+            self.client.clone(),
+            &secret_name,
+            &self.webhook.spec.namespace,
+        )
+        .await?;
+        let issued = read_tls_secret(secret)?;
+        validate_provided(&self.webhook, &issued.certificate.0)?;
+
+        info!(
+            "Certificate for {} read from secret {}",
+            self.webhook.name_any().to_lowercase(),
+            secret_name
+        );
+        Ok(Issuance::Issued(issued))
+    }
+}
@@ -0,0 +1,213 @@
+use async_trait::async_trait;
+use k8s_openapi::api::core::v1::Secret;
+use k8s_openapi::ByteString;
+use kube::core::ResourceExt;
+use kube::Client;
+use rand::rngs::OsRng;
+use rcgen::{
+    CertificateParams, DistinguishedName, DnType, KeyPair, SanType, SignatureAlgorithm,
+    PKCS_ECDSA_P256_SHA256, PKCS_ECDSA_P384_SHA384, PKCS_ED25519, PKCS_RSA_SHA256,
+};
+use rsa::pkcs8::{EncodePrivateKey, LineEnding};
+use rsa::RsaPrivateKey;
+use std::net::IpAddr;
+
+use super::cert_manager::CertManagerIssuer;
+use super::existing::ExistingSecretIssuer;
+use super::kubelet::KubeletCsrIssuer;
+use super::managed_ca::ManagedCaIssuer;
+use super::self_signed::SelfSignedCaIssuer;
+use crate::controller::Error;
+use crate::crd::{IssuerKind, KeyAlgorithm, Stage, WebhookHelper};
+use crate::operator::service_name;
+
+const DEFAULT_CLUSTER_DOMAIN: &str = "cluster.local";
+const DEFAULT_TIMEOUT_SECONDS: i64 = 300;
+
+/// PEM encoded material every issuer hands back to the `CertificateStage`
+#[derive(Clone)]
+pub struct IssuedCertificate {
+    pub certificate: ByteString,
+    pub private_key: ByteString,
+    pub ca: Option<ByteString>,
+}
+
+/// Outcome of one issuance step
+#[allow(clippy::large_enum_variant)]
+pub enum Issuance {
+    /// The certificate is ready to be stored
+    Issued(IssuedCertificate),
+    /// Issuance continues from this stage on a later reconcile
+    Pending(Stage),
+}
+
+#[async_trait]
+pub trait Issuer: Send + Sync {
+    /// Start issuing a new serving certificate for the helper
+    async fn issue(&mut self) -> Result<Issuance, Error>;
+
+    /// Continue an issuance that was left pending at `stage`
+    async fn resume(&mut self, stage: &Stage) -> Result<Issuance, Error> {
+        Err(Error::UnknownOperation(format!(
+            "Unable to resume certificate issuance from {}",
+            stage
+        )))
+    }
+
+    /// Clean up anything the issuer created besides the helper's secret
+    async fn delete(&self) -> Result<(), Error> {
+        Ok(())
+    }
+}
+
+/// Select the issuer configured on the helper, `namespace` is where the operator keeps its own state
+pub fn issuer_for(client: Client, webhook: WebhookHelper, namespace: String) -> Box<dyn Issuer> {
+    let kind = webhook
+        .spec
+        .issuer
+        .as_ref()
+        .map(|i| i.kind.clone())
+        .unwrap_or_default();
+
+    match kind {
+        IssuerKind::KubeletCsr => Box::new(KubeletCsrIssuer::new(client, webhook)),
+        IssuerKind::SelfSignedCa => Box::new(SelfSignedCaIssuer::new(client, webhook)),
+        IssuerKind::ExistingSecret => Box::new(ExistingSecretIssuer::new(client, webhook)),
+        IssuerKind::CertManager => Box::new(CertManagerIssuer::new(client, webhook)),
+        IssuerKind::ManagedCa => Box::new(ManagedCaIssuer::new(client, webhook, namespace)),
+    }
+}
+
+/// DNS names the API server may use to reach the helper's service, plus any extra DNS SANs
+pub fn dns_names(webhook: &WebhookHelper) -> Result<Vec<String>, Error> {
+    let service = service_name(webhook)?;
+    let namespace = &webhook.spec.namespace;
+    let certificate = webhook.spec.certificate.clone().unwrap_or_default();
+    let cluster_domain = certificate
+        .cluster_domain
+        .unwrap_or(DEFAULT_CLUSTER_DOMAIN.into());
+
+    let mut names = vec![
+        service.clone(),
+        format!("{}.{}", service, namespace),
+        format!("{}.{}.svc", service, namespace),
+        format!("{}.{}.svc.{}", service, namespace, cluster_domain),
+    ];
+    for san in certificate.extra_sans.unwrap_or_default() {
+        if san.parse::<IpAddr>().is_err() && !names.contains(&san) {
+            names.push(san);
+        };
+    }
+    Ok(names)
+}
+
+/// Extra IP address SANs listed in the spec
+pub fn ip_addresses(webhook: &WebhookHelper) -> Vec<IpAddr> {
+    webhook
+        .spec
+        .certificate
+        .as_ref()
+        .and_then(|c| c.extra_sans.clone())
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|san| san.parse::<IpAddr>().ok())
+        .collect()
+}
+
+/// Generate the serving key pair requested in the spec
+pub fn key_pair(webhook: &WebhookHelper) -> Result<(KeyPair, &'static SignatureAlgorithm), Error> {
+    let certificate = webhook.spec.certificate.clone().unwrap_or_default();
+    match certificate.algorithm.unwrap_or_default() {
+        KeyAlgorithm::Ecdsa => {
+            let alg = match certificate.key_size {
+                Some(384) => &PKCS_ECDSA_P384_SHA384,
+                _ => &PKCS_ECDSA_P256_SHA256,
+            };
+            Ok((KeyPair::generate(alg)?, alg))
+        }
+        KeyAlgorithm::Ed25519 => Ok((KeyPair::generate(&PKCS_ED25519)?, &PKCS_ED25519)),
+        KeyAlgorithm::Rsa => {
+            let bits = certificate.key_size.unwrap_or(2048) as usize;
+            let key = RsaPrivateKey::new(&mut OsRng, bits)
+                .map_err(|e| Error::UnableToGenerateKey(e.to_string()))?;
+            let pem = key
+                .to_pkcs8_pem(LineEnding::LF)
+                .map_err(|e| Error::UnableToGenerateKey(e.to_string()))?;
+            Ok((KeyPair::from_pem(&pem)?, &PKCS_RSA_SHA256))
+        }
+    }
+}
+
+pub fn serving_params(webhook: &WebhookHelper) -> Result<CertificateParams, Error> {
+    let names = dns_names(webhook)?;
+    let subject = webhook
+        .spec
+        .certificate
+        .as_ref()
+        .and_then(|c| c.subject.clone())
+        .unwrap_or_default();
+
+    let mut params: CertificateParams = Default::default();
+    let (key_pair, alg) = key_pair(webhook)?;
+    params.alg = alg;
+    params.key_pair = Some(key_pair);
+
+    params.distinguished_name = DistinguishedName::new();
+    params.distinguished_name.push(
+        DnType::CommonName,
+        subject.common_name.unwrap_or(names[2].clone()),
+    );
+    if let Some(o) = subject.organization {
+        params.distinguished_name.push(DnType::OrganizationName, o);
+    };
+    if let Some(ou) = subject.organizational_unit {
+        params
+            .distinguished_name
+            .push(DnType::OrganizationalUnitName, ou);
+    };
+    if let Some(c) = subject.country {
+        params.distinguished_name.push(DnType::CountryName, c);
+    };
+    if let Some(st) = subject.province {
+        params
+            .distinguished_name
+            .push(DnType::StateOrProvinceName, st);
+    };
+    if let Some(l) = subject.locality {
+        params.distinguished_name.push(DnType::LocalityName, l);
+    };
+
+    params.subject_alt_names = names.into_iter().map(SanType::DnsName).collect();
+    params
+        .subject_alt_names
+        .extend(ip_addresses(webhook).into_iter().map(SanType::IpAddress));
+    Ok(params)
+}
+
+/// Read the serving material out of a secret using the standard `tls.crt`, `tls.key` and `ca.crt` keys
+pub fn read_tls_secret(secret: Secret) -> Result<IssuedCertificate, Error> {
+    let name = secret.name_any();
+    let data = secret.data.unwrap_or_default();
+    let certificate = data.get("tls.crt").cloned().ok_or_else(|| {
+        Error::UnableToCreateObject(format!("Secret {} does not contain tls.crt", name))
+    })?;
+    let private_key = data.get("tls.key").cloned().ok_or_else(|| {
+        Error::UnableToCreateObject(format!("Secret {} does not contain tls.key", name))
+    })?;
+
+    Ok(IssuedCertificate {
+        certificate,
+        private_key,
+        ca: data.get("ca.crt").cloned(),
+    })
+}
+
+/// Seconds the issuer may take before the helper is marked as failed
+pub fn timeout_seconds(webhook: &WebhookHelper) -> i64 {
+    webhook
+        .spec
+        .issuer
+        .as_ref()
+        .and_then(|i| i.timeout_seconds)
+        .unwrap_or(DEFAULT_TIMEOUT_SECONDS)
+}
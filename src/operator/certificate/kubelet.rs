@@ -0,0 +1,307 @@
+use async_trait::async_trait;
+use chrono::Utc;
+use k8s_openapi::api::certificates::v1::{
+    CertificateSigningRequest, CertificateSigningRequestCondition, CertificateSigningRequestSpec,
+    CertificateSigningRequestStatus,
+};
+use k8s_openapi::api::core::v1::Secret;
+use k8s_openapi::ByteString;
+use kube::core::ResourceExt;
+use kube::Client;
+use kube::{core::ObjectMeta, Api};
+use rcgen::{date_time_ymd, Certificate, DistinguishedName, DnType};
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::info;
+
+use super::duration_seconds;
+use super::issuer::{serving_params, timeout_seconds, Issuance, IssuedCertificate, Issuer};
+use crate::controller::Error;
+use crate::crd::{Stage, WebhookHelper};
+use crate::metrics;
+use crate::operator::{
+    apply_operation, perform_cluster_get, perform_cluster_operation, perform_get,
+    perform_operation, set_managed_by, set_owner, Operation,
+};
+
+/// Signs the serving certificate through the `kubernetes.io/kubelet-serving` CSR flow
+///
+/// Each step returns to the controller so the reconcile loop is never blocked while
+/// the signer works, the private key waits in a `<name>-csr` secret in the meantime.
+pub struct KubeletCsrIssuer {
+    client: Client,
+    webhook: WebhookHelper,
+    cert: Option<Certificate>,
+}
+
+impl KubeletCsrIssuer {
+    pub fn new(client: Client, webhook: WebhookHelper) -> KubeletCsrIssuer {
+        KubeletCsrIssuer {
+            client,
+            webhook,
+            cert: None,
+        }
+    }
+
+    fn name(&self) -> String {
+        self.webhook.name_any().to_lowercase()
+    }
+
+    fn key_secret_name(&self) -> String {
+        format!("{}-csr", self.name())
+    }
+
+    async fn generate_cert(&mut self) -> Result<(), Error> {
+        let mut params = serving_params(&self.webhook)?;
+        // the kubelet-serving signer only accepts node identities
+        params.distinguished_name = DistinguishedName::new();
+        params
+            .distinguished_name
+            .push(DnType::OrganizationName, "system:nodes");
+        params.distinguished_name.push(
+            DnType::CommonName,
+            format!("system:node:{}", self.webhook.name_any().to_lowercase()),
+        );
+        params.not_before = date_time_ymd(1975, 1, 1);
+        params.not_after = date_time_ymd(4096, 1, 1);
+
+        self.cert = Some(Certificate::from_params(params)?);
+        Ok(())
+    }
+
+    /// Keep the private key until the signed certificate comes back
+    async fn store_key(&self) -> Result<(), Error> {
+        let mut data: BTreeMap<String, ByteString> = BTreeMap::new();
+        data.insert(
+            "tls.key".into(),
+            ByteString(
+                self.cert
+                    .as_ref()
+                    .unwrap()
+                    .serialize_private_key_pem()
+                    .into_bytes(),
+            ),
+        );
+        let mut secret = Secret {
+            type_: Some("Opaque".into()),
+            metadata: ObjectMeta {
+                name: Some(self.key_secret_name()),
+                namespace: Some(self.webhook.spec.namespace.clone()),
+                ..Default::default()
+            },
+            data: Some(data),
+            ..Default::default()
+        };
+        set_managed_by(&mut secret);
+        set_owner(&mut secret, &self.webhook);
+
+        perform_operation(self.client.clone(), apply_operation(&self.webhook), &secret).await?;
+        Ok(())
+    }
+
+    async fn load_key(&self) -> Result<ByteString, Error> {
+        let secret: Secret = perform_get(
+            self.client.clone(),
+            &self.key_secret_name(),
+            &self.webhook.spec.namespace,
+        )
+        .await?;
+        secret
+            .data
+            .and_then(|d| d.get("tls.key").cloned())
+            .ok_or_else(|| {
+                Error::UnableToCreateObject(format!(
+                    "Secret {} does not contain tls.key",
+                    self.key_secret_name()
+                ))
+            })
+    }
+
+    async fn create_csr(&mut self) -> Result<(), Error> {
+        let duration = duration_seconds(&self.webhook.spec);
+        let expiration_seconds = i32::try_from(duration).map_err(|_| {
+            Error::UnableToCreateObject(format!("duration_seconds {} is too large", duration))
+        })?;
+        let raw_csr = self.cert.as_ref().unwrap().serialize_request_pem()?;
+        let mut request = CertificateSigningRequest {
+            metadata: ObjectMeta {
+                name: Some(self.name()),
+                ..Default::default()
+            },
+            spec: CertificateSigningRequestSpec {
+                expiration_seconds: Some(expiration_seconds),
+                signer_name: "kubernetes.io/kubelet-serving".into(),
+                request: ByteString(raw_csr.into_bytes().to_vec()),
+                usages: Some(vec![
+                    "key encipherment".into(),
+                    "digital signature".into(),
+                    "server auth".into(),
+                ]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        set_owner(&mut request, &self.webhook);
+        let _response =
+            perform_cluster_operation(self.client.clone(), Operation::Create, &request).await?;
+        info!("Certificate {} created", self.name());
+        Ok(())
+    }
+
+    async fn approve_csr(&self) -> Result<(), Error> {
+        let mut request: CertificateSigningRequest =
+            perform_cluster_get(self.client.clone(), &self.name()).await?;
+
+        request.status = Some(CertificateSigningRequestStatus {
+            certificate: None,
+            conditions: Some(vec![CertificateSigningRequestCondition {
+                type_: String::from("Approved"),
+                message: Some(String::from("Approved by webhook-helper")),
+                reason: Some(String::from("WebHelperApproved")),
+                status: String::from("True"),
+                last_transition_time: None,
+                last_update_time: None,
+            }]),
+        });
+
+        let body: Vec<u8> = serde_json::to_vec(&request)?;
+        let url = format!(
+            "/apis/certificates.k8s.io/v1/certificatesigningrequests/{}/approval",
+            self.name()
+        );
+        let req = http::request::Request::put(url).body(body)?;
+        let _response = self
+            .client
+            .request::<CertificateSigningRequest>(req)
+            .await?;
+
+        info!("Certificate {} approved", self.name());
+        Ok(())
+    }
+
+    /// The signed certificate and how long the signer took, or `None` while it has not answered
+    async fn signed_cert(&self) -> Result<Option<(ByteString, Duration)>, Error> {
+        let csr_api: Api<CertificateSigningRequest> = Api::all(self.client.clone());
+        let request = csr_api.get_approval(&self.name()).await?;
+
+        let created = request
+            .metadata
+            .creation_timestamp
+            .clone()
+            .map(|t| t.0)
+            .unwrap_or_else(Utc::now);
+        let status = request.status.clone().unwrap_or_default();
+        if let Some(certificate) = status.certificate {
+            let waited = (Utc::now() - created).to_std().unwrap_or_default();
+            return Ok(Some((certificate, waited)));
+        };
+
+        // the signer will never answer a denied or failed request
+        for condition in status.conditions.unwrap_or_default() {
+            if (condition.type_ == "Denied" || condition.type_ == "Failed")
+                && condition.status == "True"
+            {
+                self.delete().await?;
+                return Err(Error::CertificateRejected(
+                    condition.reason.unwrap_or(condition.type_.clone()),
+                    condition.message.unwrap_or(format!(
+                        "CertificateSigningRequest {} {}",
+                        self.name(),
+                        condition.type_.to_lowercase()
+                    )),
+                ));
+            };
+        }
+
+        let timeout = timeout_seconds(&self.webhook);
+        if Utc::now() - created > chrono::Duration::seconds(timeout) {
+            self.delete().await?;
+            return Err(Error::CertificateNotIssued(format!(
+                "CertificateSigningRequest {} was not issued within {}s",
+                self.name(),
+                timeout
+            )));
+        };
+        Ok(None)
+    }
+
+    async fn delete_csr(&self) -> Result<(), Error> {
+        match perform_cluster_get::<CertificateSigningRequest>(self.client.clone(), &self.name())
+            .await
+        {
+            Ok(csr_request) => {
+                perform_cluster_operation(self.client.clone(), Operation::Delete, &csr_request)
+                    .await?;
+                info!("CSR {} deleted", self.name());
+            }
+            Err(kube::Error::Api(e)) if e.code == 404 => {}
+            Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+        };
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Issuer for KubeletCsrIssuer {
+    async fn issue(&mut self) -> Result<Issuance, Error> {
+        // a request left over from an earlier attempt would block the create
+        self.delete_csr().await?;
+        self.generate_cert().await?;
+        self.store_key().await?;
+        self.create_csr().await?;
+
+        Ok(Issuance::Pending(Stage::CsrCreated(self.name())))
+    }
+
+    async fn resume(&mut self, stage: &Stage) -> Result<Issuance, Error> {
+        match stage {
+            Stage::CsrCreated(_) => {
+                self.approve_csr().await?;
+                Ok(Issuance::Pending(Stage::CsrApproved(self.name())))
+            }
+            Stage::CsrApproved(_) => match self.signed_cert().await? {
+                Some((_, waited)) => {
+                    metrics::csr_issued(waited);
+                    Ok(Issuance::Pending(Stage::CertificateIssued(self.name())))
+                }
+                None => Ok(Issuance::Pending(Stage::CsrApproved(self.name()))),
+            },
+            Stage::CertificateIssued(_) => {
+                let certificate = match self.signed_cert().await? {
+                    Some((c, _)) => c,
+                    None => return Ok(Issuance::Pending(Stage::CsrApproved(self.name()))),
+                };
+                let private_key = self.load_key().await?;
+                self.delete().await?;
+
+                Ok(Issuance::Issued(IssuedCertificate {
+                    certificate,
+                    private_key,
+                    ca: None,
+                }))
+            }
+            _ => Err(Error::UnknownOperation(format!(
+                "Unable to resume certificate issuance from {}",
+                stage
+            ))),
+        }
+    }
+
+    async fn delete(&self) -> Result<(), Error> {
+        self.delete_csr().await?;
+        match perform_get::<Secret>(
+            self.client.clone(),
+            &self.key_secret_name(),
+            &self.webhook.spec.namespace,
+        )
+        .await
+        {
+            Ok(secret) => {
+                perform_operation(self.client.clone(), Operation::Delete, &secret).await?;
+            }
+            Err(kube::Error::Api(e)) if e.code == 404 => {}
+            Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+        };
+        Ok(())
+    }
+}
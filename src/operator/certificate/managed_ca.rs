@@ -0,0 +1,131 @@
+use async_trait::async_trait;
+use k8s_openapi::api::core::v1::Secret;
+use k8s_openapi::ByteString;
+use kube::core::{ObjectMeta, ResourceExt};
+use kube::Client;
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DistinguishedName, DnType,
+    ExtendedKeyUsagePurpose, IsCa, KeyPair, KeyUsagePurpose,
+};
+use std::collections::BTreeMap;
+use time::{Duration, OffsetDateTime};
+use tracing::info;
+
+use super::duration_seconds;
+use super::issuer::{read_tls_secret, serving_params, Issuance, IssuedCertificate, Issuer};
+use crate::controller::Error;
+use crate::crd::WebhookHelper;
+use crate::operator::{perform_get, perform_operation, Operation};
+
+/// Secret in the operator namespace holding the managed CA
+pub const CA_SECRET_NAME: &str = "webhook-helper-ca";
+const CA_DURATION_DAYS: i64 = 3650;
+
+/// Signs serving certificates with a CA persisted in the operator namespace
+pub struct ManagedCaIssuer {
+    client: Client,
+    webhook: WebhookHelper,
+    namespace: String,
+}
+
+impl ManagedCaIssuer {
+    pub fn new(client: Client, webhook: WebhookHelper, namespace: String) -> ManagedCaIssuer {
+        ManagedCaIssuer {
+            client,
+            webhook,
+            namespace,
+        }
+    }
+
+    async fn create_ca(&self) -> Result<Secret, Error> {
+        let mut params = CertificateParams::default();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params.distinguished_name = DistinguishedName::new();
+        params
+            .distinguished_name
+            .push(DnType::OrganizationName, "webhook-helper");
+        params
+            .distinguished_name
+            .push(DnType::CommonName, "webhook-helper-ca");
+        params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+        params.not_before = OffsetDateTime::now_utc() - Duration::minutes(5);
+        params.not_after = OffsetDateTime::now_utc() + Duration::days(CA_DURATION_DAYS);
+        let ca = Certificate::from_params(params)?;
+
+        let mut data: BTreeMap<String, ByteString> = BTreeMap::new();
+        data.insert(
+            "tls.crt".into(),
+            ByteString(ca.serialize_pem()?.into_bytes()),
+        );
+        data.insert(
+            "tls.key".into(),
+            ByteString(ca.serialize_private_key_pem().into_bytes()),
+        );
+
+        let secret = Secret {
+            type_: Some("kubernetes.io/tls".into()),
+            metadata: ObjectMeta {
+                name: Some(CA_SECRET_NAME.into()),
+                namespace: Some(self.namespace.clone()),
+                ..Default::default()
+            },
+            data: Some(data),
+            ..Default::default()
+        };
+
+        let result = perform_operation(self.client.clone(), Operation::Create, &secret).await?;
+        info!("Managed CA {} created", result.name_any());
+        Ok(result)
+    }
+
+    /// Load the managed CA, creating it on first use
+    async fn load_ca(&self) -> Result<(Certificate, ByteString), Error> {
+        let secret: Secret = match perform_get(self.client.clone(), CA_SECRET_NAME, &self.namespace)
+            .await
+        {
+            Ok(s) => s,
+            Err(kube::Error::Api(e)) if e.code == 404 => match self.create_ca().await {
+                Ok(s) => s,
+                // another worker created it first
+                Err(Error::WebhookHelperCreationFailed(kube::Error::Api(e))) if e.code == 409 => {
+                    perform_get(self.client.clone(), CA_SECRET_NAME, &self.namespace).await?
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+        };
+
+        let stored = read_tls_secret(secret)?;
+        let key_pair = KeyPair::from_pem(&String::from_utf8_lossy(&stored.private_key.0))?;
+        let params = CertificateParams::from_ca_cert_pem(
+            &String::from_utf8_lossy(&stored.certificate.0),
+            key_pair,
+        )?;
+
+        Ok((Certificate::from_params(params)?, stored.certificate))
+    }
+}
+
+#[async_trait]
+impl Issuer for ManagedCaIssuer {
+    async fn issue(&mut self) -> Result<Issuance, Error> {
+        let (ca, ca_pem) = self.load_ca().await?;
+
+        let mut params = serving_params(&self.webhook)?;
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        params.not_before = OffsetDateTime::now_utc() - Duration::minutes(5);
+        params.not_after =
+            OffsetDateTime::now_utc() + Duration::seconds(duration_seconds(&self.webhook.spec));
+        let cert = Certificate::from_params(params)?;
+
+        info!(
+            "Certificate {} signed by managed CA",
+            self.webhook.name_any().to_lowercase()
+        );
+        Ok(Issuance::Issued(IssuedCertificate {
+            certificate: ByteString(cert.serialize_pem_with_signer(&ca)?.into_bytes()),
+            private_key: ByteString(cert.serialize_private_key_pem().into_bytes()),
+            ca: Some(ca_pem),
+        }))
+    }
+}
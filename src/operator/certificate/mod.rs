@@ -0,0 +1,382 @@
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::Secret;
+use k8s_openapi::ByteString;
+use kube::core::ObjectMeta;
+use kube::core::ResourceExt;
+use kube::Client;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tracing::info;
+
+use crate::controller::Error;
+use crate::crd::{Stage, TlsSecretSpec, WebhookHelper};
+
+use super::perform_operation;
+use super::{
+    apply_operation, determine_stage, patch_status, perform_get, set_managed_by, set_owner,
+    update_status, Operation,
+};
+
+mod cert_manager;
+mod existing;
+mod issuer;
+mod kubelet;
+mod managed_ca;
+mod provided;
+mod self_signed;
+mod utils;
+pub use issuer::{issuer_for, Issuance, IssuedCertificate, Issuer};
+pub use provided::{provided_ca, read_provided, DEFAULT_CA_KEY, DEFAULT_CERT_KEY, DEFAULT_KEY_KEY};
+pub use utils::{
+    certificate_details, certificate_not_after, cluster_ca, duration_seconds, renew_before_seconds,
+    validate_certificate,
+};
+
+pub struct CertificateStage {
+    client: Client,
+    operation: Operation,
+    webhook: WebhookHelper,
+    namespace: String,
+    issued: Option<IssuedCertificate>,
+    secret: Option<Secret>,
+    renewed: bool,
+    pending: bool,
+}
+
+impl CertificateStage {
+    pub fn new(
+        client: Client,
+        operation: Operation,
+        webhook: WebhookHelper,
+        namespace: String,
+    ) -> CertificateStage {
+        CertificateStage {
+            client,
+            operation,
+            webhook,
+            namespace,
+            issued: None,
+            secret: None,
+            renewed: false,
+            pending: false,
+        }
+    }
+
+    pub async fn run(&mut self) -> Result<(), Error> {
+        match self.operation {
+            Operation::Bootstrap => {
+                let mut issuance = self.issuer().issue().await?;
+                // nothing reconciles the operator's own certificate, so wait in place
+                while let Issuance::Pending(stage) = issuance {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                    issuance = self.issuer().resume(&stage).await?;
+                }
+                if let Issuance::Issued(issued) = issuance {
+                    self.issued = Some(issued);
+                };
+                self.create_secret().await?;
+                return Ok(());
+            }
+            Operation::Create | Operation::Update => {
+                if let Some(tls_secret) = self.webhook.spec.tls_secret.clone() {
+                    return self.use_provided(&tls_secret).await;
+                };
+                if !self.issue().await? {
+                    return Ok(());
+                };
+            }
+            Operation::Delete => {
+                // a provided secret belongs to the user
+                if self.webhook.spec.tls_secret.is_some() {
+                    return Ok(());
+                };
+                if let Some(status) = self.webhook.status.clone() {
+                    if let Some(secret) = status.certificate {
+                        match perform_get::<Secret>(
+                            self.client.clone(),
+                            &secret,
+                            &self.webhook.spec.namespace,
+                        )
+                        .await
+                        {
+                            Ok(secret) => self.secret = Some(secret),
+                            // already deleted, nothing to clean up
+                            Err(kube::Error::Api(e)) if e.code == 404 => {}
+                            Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+                        };
+                    };
+                };
+                self.delete().await?;
+                return Ok(());
+            }
+            _ => return Ok(()),
+        };
+
+        if let Operation::Update = self.operation {
+            self.update_secret().await?;
+            self.record_details().await?;
+            info!(
+                "Certificate {} renewed",
+                self.webhook.name_any().to_lowercase()
+            );
+            return Ok(());
+        };
+
+        self.create_secret().await?;
+        let secret_name = match self.secret.clone() {
+            Some(secret) => secret.name_any(),
+            None => "<unknown>".into(),
+        };
+        update_status(
+            self.client.clone(),
+            Stage::CertificateCreated(secret_name),
+            self.webhook.clone(),
+        )
+        .await?;
+        self.record_details().await
+    }
+
+    fn issuer(&self) -> Box<dyn Issuer> {
+        issuer_for(
+            self.client.clone(),
+            self.webhook.clone(),
+            self.namespace.clone(),
+        )
+    }
+
+    /// Start or continue issuance, returns whether a certificate is ready to store
+    async fn issue(&mut self) -> Result<bool, Error> {
+        let current = determine_stage(self.client.clone(), self.webhook.clone()).await?;
+        let mut issuer = self.issuer();
+        let issuance = match current {
+            Stage::CsrCreated(_)
+            | Stage::CsrApproved(_)
+            | Stage::CertificateIssued(_)
+            | Stage::CertificateRequested(_) => issuer.resume(&current).await,
+            _ => issuer.issue().await,
+        };
+
+        match issuance {
+            Ok(Issuance::Issued(issued)) => {
+                self.pending = false;
+                self.issued = Some(issued);
+                Ok(true)
+            }
+            Ok(Issuance::Pending(next)) => {
+                self.pending = true;
+                if next.to_string() != current.to_string() {
+                    update_status(self.client.clone(), next, self.webhook.clone()).await?;
+                };
+                Ok(false)
+            }
+            Err(Error::CertificateRejected(reason, message)) => {
+                self.reject(reason, message).await?;
+                Ok(false)
+            }
+            Err(Error::CertificateNotIssued(reason)) => {
+                self.pending = false;
+                update_status(
+                    self.client.clone(),
+                    Stage::CreationFailed(reason),
+                    self.webhook.clone(),
+                )
+                .await?;
+                Ok(false)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Block the helper on a certificate that cannot be used until the spec changes or a retry is requested
+    async fn reject(&mut self, reason: String, message: String) -> Result<(), Error> {
+        self.pending = false;
+        update_status(
+            self.client.clone(),
+            Stage::CertificateFailed(reason, message),
+            self.webhook.clone(),
+        )
+        .await?;
+        Ok(())
+    }
+
+    /// Mount the user's own secret as is once it passes validation
+    async fn use_provided(&mut self, tls_secret: &TlsSecretSpec) -> Result<(), Error> {
+        let (secret, issued) =
+            match read_provided(self.client.clone(), &self.webhook, tls_secret).await {
+                Ok(p) => p,
+                Err(Error::CertificateRejected(reason, message)) => {
+                    return self.reject(reason, message).await
+                }
+                Err(e) => return Err(e),
+            };
+        self.issued = Some(issued);
+        self.secret = Some(secret);
+
+        if let Operation::Create = self.operation {
+            update_status(
+                self.client.clone(),
+                Stage::CertificateCreated(tls_secret.name.clone()),
+                self.webhook.clone(),
+            )
+            .await?;
+        };
+        self.record_details().await
+    }
+
+    /// Whether issuance is waiting on the issuer and the helper should be requeued
+    pub fn pending(&self) -> bool {
+        self.pending
+    }
+
+    async fn build_secret(&self) -> Result<Secret, Error> {
+        let issued = self.issued.clone().unwrap();
+        // issuers without their own CA are signed by the cluster
+        let ca = match issued.ca {
+            Some(ca) => ca,
+            None => ByteString(cluster_ca(self.client.clone()).await?.into_bytes()),
+        };
+
+        let mut data: BTreeMap<String, ByteString> = BTreeMap::new();
+        data.insert("tls.key".into(), issued.private_key);
+        data.insert("tls.crt".into(), issued.certificate);
+        data.insert("ca.crt".into(), ca);
+
+        let mut secret = Secret {
+            type_: Some("kubernetes.io/tls".into()),
+            metadata: ObjectMeta {
+                name: Some(self.webhook.name_any().to_lowercase().to_string()),
+                namespace: Some(self.webhook.spec.namespace.clone()),
+                ..Default::default()
+            },
+            data: Some(data),
+            ..Default::default()
+        };
+        set_managed_by(&mut secret);
+        set_owner(&mut secret, &self.webhook);
+        Ok(secret)
+    }
+
+    async fn create_secret(&mut self) -> Result<(), Error> {
+        let secret = self.build_secret().await?;
+        let result =
+            perform_operation(self.client.clone(), apply_operation(&self.webhook), &secret).await?;
+        self.secret = Some(result);
+
+        info!("Secret {} created", self.webhook.name_any().to_lowercase());
+        Ok(())
+    }
+
+    async fn update_secret(&mut self) -> Result<(), Error> {
+        let mut secret = self.build_secret().await?;
+        match perform_operation(self.client.clone(), Operation::Get, &secret).await {
+            // the type of a secret is immutable, so older secrets are replaced
+            Ok(current) if current.type_ != secret.type_ => {
+                // keep labels others put on the old secret, ours take precedence
+                let mut labels = current.metadata.labels.clone().unwrap_or_default();
+                labels.extend(secret.metadata.labels.take().unwrap_or_default());
+                secret.metadata.labels = Some(labels);
+                perform_operation(self.client.clone(), Operation::Delete, &current).await?;
+            }
+            Ok(_) => {}
+            Err(Error::WebhookHelperCreationFailed(kube::Error::Api(e))) if e.code == 404 => {}
+            Err(e) => return Err(e),
+        };
+        let result =
+            perform_operation(self.client.clone(), apply_operation(&self.webhook), &secret).await?;
+        self.secret = Some(result);
+
+        info!("Secret {} updated", self.webhook.name_any().to_lowercase());
+        Ok(())
+    }
+
+    async fn record_details(&mut self) -> Result<(), Error> {
+        if let Some(issued) = self.issued.as_ref() {
+            let secret_name = match self.secret.as_ref() {
+                Some(secret) => secret.name_any(),
+                None => "<unknown>".into(),
+            };
+            let details = certificate_details(&secret_name, &issued.certificate.0)?;
+            let previous = self
+                .webhook
+                .status
+                .as_ref()
+                .and_then(|s| s.certificate_details.clone())
+                .map(|d| d.sha256_fingerprint);
+            self.renewed = previous != Some(details.sha256_fingerprint.clone());
+            patch_status(self.client.clone(), &self.webhook, |status| {
+                status.certificate_details = Some(details)
+            })
+            .await?;
+        };
+        Ok(())
+    }
+
+    /// Whether the last run produced a different certificate than the one in status
+    pub fn renewed(&self) -> bool {
+        self.renewed
+    }
+
+    /// Whether the secret issued for the helper was deleted and has to be issued again
+    pub async fn secret_missing(&self) -> Result<bool, Error> {
+        if self.webhook.spec.tls_secret.is_some() {
+            return Ok(false);
+        };
+        match perform_get::<Secret>(
+            self.client.clone(),
+            &self.webhook.name_any().to_lowercase(),
+            &self.webhook.spec.namespace,
+        )
+        .await
+        {
+            Ok(_) => Ok(false),
+            Err(kube::Error::Api(e)) if e.code == 404 => Ok(true),
+            Err(e) => Err(Error::WebhookHelperCreationFailed(e)),
+        }
+    }
+
+    /// Time left until the mounted certificate enters its renewal window
+    pub async fn time_until_renewal(&self) -> Result<Duration, Error> {
+        let not_after = match self
+            .webhook
+            .status
+            .as_ref()
+            .and_then(|s| s.certificate_details.clone())
+        {
+            Some(d) => DateTime::parse_from_rfc3339(&d.not_after)
+                .map_err(|e| Error::UnableToParseCertificate(e.to_string()))?
+                .with_timezone(&Utc),
+            // a provided secret is re-validated instead
+            None if self.webhook.spec.tls_secret.is_some() => return Ok(Duration::ZERO),
+            None => {
+                let secret: Secret = perform_get(
+                    self.client.clone(),
+                    &self.webhook.name_any().to_lowercase(),
+                    &self.webhook.spec.namespace,
+                )
+                .await?;
+                match secret.data.and_then(|d| d.get("tls.crt").cloned()) {
+                    Some(c) => certificate_not_after(&c.0)?,
+                    None => return Ok(Duration::ZERO),
+                }
+            }
+        };
+
+        let renew_before = renew_before_seconds(&self.webhook.spec);
+        let renew_at = not_after - chrono::Duration::seconds(renew_before);
+
+        Ok((renew_at - Utc::now()).to_std().unwrap_or(Duration::ZERO))
+    }
+
+    #[allow(dead_code)]
+    pub async fn get_secret(&self) -> Option<Secret> {
+        self.secret.clone()
+    }
+
+    async fn delete(&self) -> Result<(), Error> {
+        if let Some(secret) = self.secret.clone() {
+            perform_operation(self.client.clone(), Operation::Delete, &secret).await?;
+        };
+        self.issuer().delete().await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+use chrono::Utc;
+use k8s_openapi::api::core::v1::{ConfigMap, Secret};
+use k8s_openapi::ByteString;
+use kube::Client;
+use std::net::IpAddr;
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::parse_x509_pem;
+
+use super::issuer::{dns_names, ip_addresses, IssuedCertificate};
+use super::utils::san_ip;
+use crate::controller::Error;
+use crate::crd::{TlsSecretSpec, WebhookHelper};
+use crate::operator::perform_get;
+
+pub const DEFAULT_CERT_KEY: &str = "tls.crt";
+pub const DEFAULT_KEY_KEY: &str = "tls.key";
+pub const DEFAULT_CA_KEY: &str = "ca.crt";
+
+/// Read and validate the secret referenced by `tls_secret`, with the CA resolved from the secret or ConfigMap
+pub async fn read_provided(
+    client: Client,
+    webhook: &WebhookHelper,
+    tls_secret: &TlsSecretSpec,
+) -> Result<(Secret, IssuedCertificate), Error> {
+    let namespace = &webhook.spec.namespace;
+    let secret: Secret = match perform_get(client.clone(), &tls_secret.name, namespace).await {
+        Ok(s) => s,
+        Err(kube::Error::Api(e)) if e.code == 404 => {
+            return Err(Error::CertificateRejected(
+                "SecretNotFound".into(),
+                format!("Secret {} not found in {}", tls_secret.name, namespace),
+            ))
+        }
+        Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+    };
+
+    let data = secret.data.clone().unwrap_or_default();
+    let key = |name: &Option<String>, default: &str| -> Result<ByteString, Error> {
+        let name = name.clone().unwrap_or(default.into());
+        data.get(&name).cloned().ok_or_else(|| {
+            Error::CertificateRejected(
+                "InvalidSecret".into(),
+                format!("Secret {} does not contain {}", tls_secret.name, name),
+            )
+        })
+    };
+    let certificate = key(&tls_secret.cert_key, DEFAULT_CERT_KEY)?;
+    let private_key = key(&tls_secret.key_key, DEFAULT_KEY_KEY)?;
+    let ca = match tls_secret.ca_config_map.as_ref() {
+        Some(_) => ByteString(provided_ca(client, webhook, tls_secret).await?.into_bytes()),
+        None => key(&tls_secret.ca_key, DEFAULT_CA_KEY)?,
+    };
+
+    validate_provided(webhook, &certificate.0)?;
+
+    Ok((
+        secret,
+        IssuedCertificate {
+            certificate,
+            private_key,
+            ca: Some(ca),
+        },
+    ))
+}
+
+/// CA bundle for a helper using its own secret
+pub async fn provided_ca(
+    client: Client,
+    webhook: &WebhookHelper,
+    tls_secret: &TlsSecretSpec,
+) -> Result<String, Error> {
+    let namespace = &webhook.spec.namespace;
+    match tls_secret.ca_config_map.as_ref() {
+        Some(config_map) => {
+            let key = config_map.key.clone().unwrap_or(DEFAULT_CA_KEY.into());
+            let cm: ConfigMap = perform_get(client, &config_map.name, namespace).await?;
+            cm.data.and_then(|d| d.get(&key).cloned()).ok_or_else(|| {
+                Error::CertificateRejected(
+                    "InvalidConfigMap".into(),
+                    format!("ConfigMap {} does not contain {}", config_map.name, key),
+                )
+            })
+        }
+        None => {
+            let key = tls_secret.ca_key.clone().unwrap_or(DEFAULT_CA_KEY.into());
+            let secret: Secret = perform_get(client, &tls_secret.name, namespace).await?;
+            match secret.data.and_then(|d| d.get(&key).cloned()) {
+                Some(ca) => Ok(String::from_utf8_lossy(&ca.0).to_string()),
+                None => Err(Error::CertificateRejected(
+                    "InvalidSecret".into(),
+                    format!("Secret {} does not contain {}", tls_secret.name, key),
+                )),
+            }
+        }
+    }
+}
+
+/// The certificate must be current and cover the service name the API server calls
+pub fn validate_provided(webhook: &WebhookHelper, pem: &[u8]) -> Result<(), Error> {
+    let invalid =
+        |message: String| Error::CertificateRejected("InvalidCertificate".into(), message);
+
+    let (_, pem) = parse_x509_pem(pem).map_err(|e| invalid(e.to_string()))?;
+    let cert = pem.parse_x509().map_err(|e| invalid(e.to_string()))?;
+
+    let now = Utc::now().timestamp();
+    if cert.validity().not_before.timestamp() > now {
+        return Err(invalid("Certificate is not valid yet".into()));
+    };
+    if cert.validity().not_after.timestamp() <= now {
+        return Err(invalid("Certificate has expired".into()));
+    };
+
+    let mut sans: Vec<String> = Vec::new();
+    let mut ips: Vec<IpAddr> = Vec::new();
+    if let Ok(Some(extension)) = cert.subject_alternative_name() {
+        for name in extension.value.general_names.iter() {
+            match name {
+                GeneralName::DNSName(d) => sans.push(d.to_lowercase()),
+                GeneralName::IPAddress(bytes) => {
+                    ips.push(san_ip(bytes).map_err(|e| invalid(e.to_string()))?)
+                }
+                _ => {}
+            }
+        }
+    };
+
+    // `<service>.<namespace>.svc` is the name the API server connects to
+    let names = dns_names(webhook)?;
+    let extra_sans = webhook
+        .spec
+        .certificate
+        .as_ref()
+        .and_then(|c| c.extra_sans.clone())
+        .unwrap_or_default();
+    let mut required = vec![names[2].clone()];
+    required.extend(names.into_iter().filter(|n| extra_sans.contains(n)));
+
+    let missing: Vec<String> = required
+        .into_iter()
+        .filter(|name| !sans.iter().any(|san| san_matches(san, name)))
+        .chain(
+            ip_addresses(webhook)
+                .into_iter()
+                .filter(|ip| !ips.contains(ip))
+                .map(|ip| ip.to_string()),
+        )
+        .collect();
+    if !missing.is_empty() {
+        return Err(invalid(format!(
+            "Certificate does not cover {}",
+            missing.join(", ")
+        )));
+    };
+    Ok(())
+}
+
+fn san_matches(san: &str, name: &str) -> bool {
+    match san.strip_prefix("*.") {
+        Some(domain) => name
+            .split_once('.')
+            .map(|(_, rest)| rest == domain)
+            .unwrap_or(false),
+        None => san == name,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{Certificate, CertificateParams};
+    use serde_json::json;
+    use time::{Duration, OffsetDateTime};
+
+    fn helper() -> WebhookHelper {
+        let spec = serde_json::from_value(json!({
+            "namespace": "ns",
+            "webhook": {},
+            "listening_port": 8443,
+            "deployment": { "metadata": { "name": "svc" } },
+        }))
+        .unwrap();
+        WebhookHelper::new("helper", spec)
+    }
+
+    fn pem(names: &[&str], not_before: Duration, not_after: Duration) -> Vec<u8> {
+        let mut params =
+            CertificateParams::new(names.iter().map(|n| n.to_string()).collect::<Vec<_>>());
+        params.not_before = OffsetDateTime::now_utc() + not_before;
+        params.not_after = OffsetDateTime::now_utc() + not_after;
+        let cert = Certificate::from_params(params).unwrap();
+        cert.serialize_pem().unwrap().into_bytes()
+    }
+
+    #[test]
+    fn accepts_a_current_certificate_for_the_service() {
+        let cert = pem(&["svc.ns.svc"], -Duration::hours(1), Duration::hours(1));
+        assert!(validate_provided(&helper(), &cert).is_ok());
+        let cert = pem(&["*.ns.svc"], -Duration::hours(1), Duration::hours(1));
+        assert!(validate_provided(&helper(), &cert).is_ok());
+    }
+
+    #[test]
+    fn rejects_a_certificate_missing_the_service() {
+        let cert = pem(&["other.ns.svc"], -Duration::hours(1), Duration::hours(1));
+        assert!(validate_provided(&helper(), &cert).is_err());
+    }
+
+    #[test]
+    fn rejects_a_certificate_outside_its_validity() {
+        let cert = pem(&["svc.ns.svc"], Duration::hours(1), Duration::hours(2));
+        assert!(validate_provided(&helper(), &cert).is_err());
+        let cert = pem(&["svc.ns.svc"], -Duration::hours(2), -Duration::hours(1));
+        assert!(validate_provided(&helper(), &cert).is_err());
+    }
+
+    #[test]
+    fn san_matches_exact_names() {
+        assert!(san_matches("svc.ns.svc", "svc.ns.svc"));
+        assert!(!san_matches("svc.ns.svc", "other.ns.svc"));
+    }
+
+    #[test]
+    fn wildcard_covers_one_label() {
+        assert!(san_matches("*.ns.svc", "svc.ns.svc"));
+        assert!(!san_matches("*.ns.svc", "a.svc.ns.svc"));
+        assert!(!san_matches("*.ns.svc", "ns.svc"));
+        assert!(!san_matches("*.svc", "svc.ns.svc"));
+    }
+}
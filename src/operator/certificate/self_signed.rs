@@ -0,0 +1,143 @@
+use async_trait::async_trait;
+use k8s_openapi::api::core::v1::Secret;
+use k8s_openapi::ByteString;
+use kube::core::{ObjectMeta, ResourceExt};
+use kube::Client;
+use rcgen::{
+    BasicConstraints, Certificate, CertificateParams, DnType, ExtendedKeyUsagePurpose, IsCa,
+    KeyPair, KeyUsagePurpose,
+};
+use std::collections::BTreeMap;
+use time::{Duration, OffsetDateTime};
+use tracing::info;
+
+use super::duration_seconds;
+use super::issuer::{read_tls_secret, serving_params, Issuance, IssuedCertificate, Issuer};
+use crate::controller::Error;
+use crate::crd::WebhookHelper;
+use crate::operator::{perform_get, perform_operation, set_managed_by, set_owner, Operation};
+
+const CA_DURATION_DAYS: i64 = 3650;
+
+/// Generates a CA for the helper on first use, kept in a secret next to the serving certificate
+/// so renewals are signed by the CA already in the `caBundle`
+pub struct SelfSignedCaIssuer {
+    client: Client,
+    webhook: WebhookHelper,
+}
+
+impl SelfSignedCaIssuer {
+    pub fn new(client: Client, webhook: WebhookHelper) -> SelfSignedCaIssuer {
+        SelfSignedCaIssuer { client, webhook }
+    }
+
+    fn ca_secret_name(&self) -> String {
+        format!("{}-ca", self.webhook.name_any().to_lowercase())
+    }
+
+    async fn create_ca(&self) -> Result<Secret, Error> {
+        let mut params = CertificateParams::default();
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        params.distinguished_name.push(
+            DnType::CommonName,
+            format!(
+                "webhook-helper-ca:{}",
+                self.webhook.name_any().to_lowercase()
+            ),
+        );
+        params.key_usages = vec![KeyUsagePurpose::KeyCertSign, KeyUsagePurpose::CrlSign];
+        params.not_before = OffsetDateTime::now_utc() - Duration::minutes(5);
+        params.not_after = OffsetDateTime::now_utc() + Duration::days(CA_DURATION_DAYS);
+        let ca = Certificate::from_params(params)?;
+
+        let mut data: BTreeMap<String, ByteString> = BTreeMap::new();
+        data.insert(
+            "tls.crt".into(),
+            ByteString(ca.serialize_pem()?.into_bytes()),
+        );
+        data.insert(
+            "tls.key".into(),
+            ByteString(ca.serialize_private_key_pem().into_bytes()),
+        );
+
+        let mut secret = Secret {
+            type_: Some("kubernetes.io/tls".into()),
+            metadata: ObjectMeta {
+                name: Some(self.ca_secret_name()),
+                namespace: Some(self.webhook.spec.namespace.clone()),
+                ..Default::default()
+            },
+            data: Some(data),
+            ..Default::default()
+        };
+        set_managed_by(&mut secret);
+        set_owner(&mut secret, &self.webhook);
+
+        let result = perform_operation(self.client.clone(), Operation::Create, &secret).await?;
+        info!("Self signed CA {} created", result.name_any());
+        Ok(result)
+    }
+
+    /// Load the helper's CA, creating it on first use
+    async fn load_ca(&self) -> Result<(Certificate, ByteString), Error> {
+        let name = self.ca_secret_name();
+        let namespace = &self.webhook.spec.namespace;
+        let secret: Secret = match perform_get(self.client.clone(), &name, namespace).await {
+            Ok(s) => s,
+            Err(kube::Error::Api(e)) if e.code == 404 => match self.create_ca().await {
+                Ok(s) => s,
+                // another worker created it first
+                Err(Error::WebhookHelperCreationFailed(kube::Error::Api(e))) if e.code == 409 => {
+                    perform_get(self.client.clone(), &name, namespace).await?
+                }
+                Err(e) => return Err(e),
+            },
+            Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+        };
+
+        let stored = read_tls_secret(secret)?;
+        let key_pair = KeyPair::from_pem(&String::from_utf8_lossy(&stored.private_key.0))?;
+        let params = CertificateParams::from_ca_cert_pem(
+            &String::from_utf8_lossy(&stored.certificate.0),
+            key_pair,
+        )?;
+
+        Ok((Certificate::from_params(params)?, stored.certificate))
+    }
+}
+
+#[async_trait]
+impl Issuer for SelfSignedCaIssuer {
+    async fn issue(&mut self) -> Result<Issuance, Error> {
+        let (ca, ca_pem) = self.load_ca().await?;
+
+        let mut params = serving_params(&self.webhook)?;
+        params.extended_key_usages = vec![ExtendedKeyUsagePurpose::ServerAuth];
+        params.not_before = OffsetDateTime::now_utc() - Duration::minutes(5);
+        params.not_after =
+            OffsetDateTime::now_utc() + Duration::seconds(duration_seconds(&self.webhook.spec));
+        let cert = Certificate::from_params(params)?;
+
+        info!(
+            "Self signed certificate {} created",
+            self.webhook.name_any().to_lowercase()
+        );
+        Ok(Issuance::Issued(IssuedCertificate {
+            certificate: ByteString(cert.serialize_pem_with_signer(&ca)?.into_bytes()),
+            private_key: ByteString(cert.serialize_private_key_pem().into_bytes()),
+            ca: Some(ca_pem),
+        }))
+    }
+    async fn delete(&self) -> Result<(), Error> {
+        if let Ok(secret) = perform_get::<Secret>(
+            self.client.clone(),
+            &self.ca_secret_name(),
+            &self.webhook.spec.namespace,
+        )
+        .await
+        {
+            perform_operation(self.client.clone(), Operation::Delete, &secret).await?;
+        };
+        Ok(())
+    }
+}
@@ -0,0 +1,310 @@
+use chrono::{DateTime, Utc};
+use k8s_openapi::api::core::v1::ConfigMap;
+use kube::Client;
+use sha2::{Digest, Sha256};
+use std::net::IpAddr;
+use x509_parser::extensions::GeneralName;
+use x509_parser::pem::parse_x509_pem;
+
+use crate::controller::Error;
+use crate::crd::{CertificateDetails, CertificateSpec, HelperSpec, IssuerKind, KeyAlgorithm};
+use crate::operator::perform_get;
+
+/// Lifetime of a serving certificate when the spec does not set one
+pub const DEFAULT_DURATION_SECONDS: i64 = 86400;
+/// Shortest lifetime every issuer accepts
+const MIN_DURATION_SECONDS: i64 = 3600;
+/// Longest lifetime, a year, well within what signers and the renewal requeue handle
+const MAX_DURATION_SECONDS: i64 = 365 * 86400;
+
+pub fn duration_seconds(spec: &HelperSpec) -> i64 {
+    spec.certificate
+        .as_ref()
+        .and_then(|c| c.duration_seconds)
+        .unwrap_or(DEFAULT_DURATION_SECONDS)
+}
+
+pub fn renew_before_seconds(spec: &HelperSpec) -> i64 {
+    spec.certificate
+        .as_ref()
+        .and_then(|c| c.renew_before_seconds)
+        .unwrap_or(duration_seconds(spec) / 3)
+}
+
+/// Root CA of the cluster, which signs `kubernetes.io/kubelet-serving` certificates
+pub async fn cluster_ca(client: Client) -> Result<String, Error> {
+    let config_map: ConfigMap = perform_get(client, "kube-root-ca.crt", "default").await?;
+    match config_map.data.and_then(|d| d.get("ca.crt").cloned()) {
+        Some(c) => Ok(c),
+        None => Err(Error::UnableToCreateObject(
+            "Cound not find signing CA".into(),
+        )),
+    }
+}
+
+pub fn certificate_not_after(pem: &[u8]) -> Result<DateTime<Utc>, Error> {
+    let (_, pem) =
+        parse_x509_pem(pem).map_err(|e| Error::UnableToParseCertificate(e.to_string()))?;
+    let cert = pem
+        .parse_x509()
+        .map_err(|e| Error::UnableToParseCertificate(e.to_string()))?;
+    DateTime::from_timestamp(cert.validity().not_after.timestamp(), 0).ok_or(
+        Error::UnableToParseCertificate("Invalid certificate expiration".into()),
+    )
+}
+
+/// Address held by an IP address SAN, 4 bytes for IPv4 and 16 for IPv6
+pub fn san_ip(bytes: &[u8]) -> Result<IpAddr, Error> {
+    if let Ok(octets) = <[u8; 4]>::try_from(bytes) {
+        return Ok(IpAddr::from(octets));
+    };
+    match <[u8; 16]>::try_from(bytes) {
+        Ok(octets) => Ok(IpAddr::from(octets)),
+        Err(_) => Err(Error::UnableToParseCertificate(format!(
+            "IP address SAN of {} bytes",
+            bytes.len()
+        ))),
+    }
+}
+
+/// Summarise a PEM certificate for the helper's status
+pub fn certificate_details(secret_name: &str, pem: &[u8]) -> Result<CertificateDetails, Error> {
+    let (_, pem) =
+        parse_x509_pem(pem).map_err(|e| Error::UnableToParseCertificate(e.to_string()))?;
+    let cert = pem
+        .parse_x509()
+        .map_err(|e| Error::UnableToParseCertificate(e.to_string()))?;
+
+    let timestamp = |t: i64| {
+        DateTime::from_timestamp(t, 0)
+            .map(|d| d.to_rfc3339())
+            .ok_or(Error::UnableToParseCertificate(
+                "Invalid certificate validity".into(),
+            ))
+    };
+
+    let mut subject_alt_names = Vec::new();
+    if let Ok(Some(extension)) = cert.subject_alternative_name() {
+        for name in extension.value.general_names.iter() {
+            match name {
+                GeneralName::DNSName(d) => subject_alt_names.push(d.to_string()),
+                GeneralName::IPAddress(bytes) => subject_alt_names.push(san_ip(bytes)?.to_string()),
+                _ => {}
+            }
+        }
+    };
+
+    let fingerprint = Sha256::digest(&pem.contents)
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<String>>()
+        .join(":");
+
+    Ok(CertificateDetails {
+        secret_name: secret_name.into(),
+        serial_number: cert.raw_serial_as_string().to_uppercase(),
+        sha256_fingerprint: fingerprint,
+        not_before: timestamp(cert.validity().not_before.timestamp())?,
+        not_after: timestamp(cert.validity().not_after.timestamp())?,
+        issuer: cert.issuer().to_string(),
+        subject_alt_names,
+    })
+}
+
+pub async fn validate_certificate(spec: &HelperSpec) -> Result<(), Error> {
+    if let Some(tls_secret) = spec.tls_secret.as_ref() {
+        if spec.issuer.is_some() {
+            return Err(Error::UnableToCreateObject(
+                "issuer cannot be set together with tls_secret".into(),
+            ));
+        };
+        if tls_secret.name.is_empty() {
+            return Err(Error::UnableToCreateObject(
+                "tls_secret requires a name".into(),
+            ));
+        };
+    };
+
+    let issuer = spec.issuer.clone().unwrap_or_default();
+    match issuer.kind {
+        IssuerKind::ExistingSecret if issuer.secret_name.is_none() => {
+            return Err(Error::UnableToCreateObject(
+                "ExistingSecret issuer requires secret_name".into(),
+            ))
+        }
+        IssuerKind::CertManager if issuer.issuer_name.is_none() => {
+            return Err(Error::UnableToCreateObject(
+                "CertManager issuer requires issuer_name".into(),
+            ))
+        }
+        IssuerKind::CertManager
+            if !matches!(
+                issuer.issuer_kind.as_deref(),
+                None | Some("Issuer") | Some("ClusterIssuer")
+            ) =>
+        {
+            return Err(Error::UnableToCreateObject(
+                "issuer_kind must be Issuer or ClusterIssuer".into(),
+            ))
+        }
+        _ => {}
+    };
+
+    let certificate = match spec.certificate.clone() {
+        Some(c) => c,
+        None => return Ok(()),
+    };
+
+    let existing = issuer.kind == IssuerKind::ExistingSecret || spec.tls_secret.is_some();
+    if existing
+        && (certificate.algorithm.is_some()
+            || certificate.key_size.is_some()
+            || certificate.duration_seconds.is_some()
+            || certificate.subject.is_some())
+    {
+        return Err(Error::UnableToCreateObject(
+            "algorithm, key_size, duration_seconds and subject cannot be set with the ExistingSecret issuer or tls_secret".into(),
+        ));
+    };
+
+    if issuer.kind == IssuerKind::KubeletCsr && certificate.subject.is_some() {
+        return Err(Error::UnableToCreateObject(
+            "subject cannot be set with the KubeletCsr issuer".into(),
+        ));
+    };
+
+    validate_key(&certificate)?;
+
+    // the lifetime of a certificate issued elsewhere is not known up front
+    if existing {
+        return match certificate.renew_before_seconds {
+            Some(renew_before) if renew_before <= 0 => Err(Error::UnableToCreateObject(
+                "renew_before_seconds must be at least 1".into(),
+            )),
+            _ => Ok(()),
+        };
+    };
+
+    let duration = duration_seconds(spec);
+    if !(MIN_DURATION_SECONDS..=MAX_DURATION_SECONDS).contains(&duration) {
+        return Err(Error::UnableToCreateObject(format!(
+            "duration_seconds must be between {} and {}",
+            MIN_DURATION_SECONDS, MAX_DURATION_SECONDS
+        )));
+    };
+
+    let renew_before = renew_before_seconds(spec);
+    if renew_before <= 0 || renew_before >= duration {
+        return Err(Error::UnableToCreateObject(format!(
+            "renew_before_seconds must be between 1 and {}",
+            duration - 1
+        )));
+    };
+
+    Ok(())
+}
+
+fn validate_key(certificate: &CertificateSpec) -> Result<(), Error> {
+    let algorithm = certificate.algorithm.clone().unwrap_or_default();
+    let valid = match (&algorithm, certificate.key_size) {
+        (_, None) => true,
+        (KeyAlgorithm::Ecdsa, Some(size)) => size == 256 || size == 384,
+        (KeyAlgorithm::Rsa, Some(size)) => size == 2048 || size == 3072 || size == 4096,
+        (KeyAlgorithm::Ed25519, Some(_)) => false,
+    };
+
+    if !valid {
+        return Err(Error::UnableToCreateObject(format!(
+            "key_size {} is not supported for {:?}",
+            certificate.key_size.unwrap_or_default(),
+            algorithm
+        )));
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crd::KeyAlgorithm;
+    use serde_json::{json, Value};
+
+    fn spec(certificate: Value, issuer: Value) -> HelperSpec {
+        serde_json::from_value(json!({
+            "namespace": "ns",
+            "webhook": {},
+            "listening_port": 8443,
+            "deployment": { "metadata": { "name": "svc" } },
+            "certificate": certificate,
+            "issuer": issuer,
+        }))
+        .unwrap()
+    }
+
+    async fn valid(certificate: Value) -> bool {
+        validate_certificate(&spec(certificate, Value::Null))
+            .await
+            .is_ok()
+    }
+
+    #[tokio::test]
+    async fn duration_must_be_within_bounds() {
+        assert!(valid(json!({ "duration_seconds": MIN_DURATION_SECONDS })).await);
+        assert!(valid(json!({ "duration_seconds": MAX_DURATION_SECONDS })).await);
+        assert!(!valid(json!({ "duration_seconds": MIN_DURATION_SECONDS - 1 })).await);
+        assert!(!valid(json!({ "duration_seconds": MAX_DURATION_SECONDS + 1 })).await);
+        assert!(valid(Value::Null).await);
+    }
+
+    #[tokio::test]
+    async fn renew_before_must_be_shorter_than_the_duration() {
+        let certificate = |renew_before: i64| json!({ "duration_seconds": 7200, "renew_before_seconds": renew_before });
+        assert!(valid(certificate(1)).await);
+        assert!(valid(certificate(7199)).await);
+        assert!(!valid(certificate(0)).await);
+        assert!(!valid(certificate(7200)).await);
+    }
+
+    #[tokio::test]
+    async fn existing_certificates_only_check_renew_before() {
+        let issuer = json!({ "kind": "ExistingSecret", "secret_name": "tls" });
+        let existing = |certificate: Value| spec(certificate, issuer.clone());
+        let long_renewal = existing(json!({ "renew_before_seconds": 10 * MAX_DURATION_SECONDS }));
+        assert!(validate_certificate(&long_renewal).await.is_ok());
+        let no_renewal = existing(json!({ "renew_before_seconds": 0 }));
+        assert!(validate_certificate(&no_renewal).await.is_err());
+        let duration = existing(json!({ "duration_seconds": 7200 }));
+        assert!(validate_certificate(&duration).await.is_err());
+    }
+
+    #[test]
+    fn san_ip_decodes_both_families() {
+        assert_eq!(
+            san_ip(&[10, 0, 0, 1]).unwrap(),
+            "10.0.0.1".parse::<IpAddr>().unwrap()
+        );
+        let mut v6 = [0u8; 16];
+        v6[15] = 1;
+        assert_eq!(san_ip(&v6).unwrap(), "::1".parse::<IpAddr>().unwrap());
+        assert!(san_ip(&[10, 0, 0]).is_err());
+        assert!(san_ip(&[]).is_err());
+    }
+
+    #[test]
+    fn key_size_must_suit_the_algorithm() {
+        let key = |algorithm: KeyAlgorithm, key_size: Option<u32>| {
+            validate_key(&CertificateSpec {
+                algorithm: Some(algorithm),
+                key_size,
+                ..Default::default()
+            })
+            .is_ok()
+        };
+        assert!(key(KeyAlgorithm::Ecdsa, Some(384)));
+        assert!(!key(KeyAlgorithm::Ecdsa, Some(2048)));
+        assert!(key(KeyAlgorithm::Rsa, Some(4096)));
+        assert!(!key(KeyAlgorithm::Rsa, Some(1024)));
+        assert!(key(KeyAlgorithm::Ed25519, None));
+        assert!(!key(KeyAlgorithm::Ed25519, Some(256)));
+    }
+}
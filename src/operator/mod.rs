@@ -6,11 +6,14 @@ mod utils;
 mod webhook;
 
 pub use backend::{validate_container_name, DeploymentStage};
-pub use certificate::CertificateStage;
+pub use certificate::{cluster_ca, provided_ca, validate_certificate, CertificateStage};
 pub use service::ServiceStage;
-pub use webhook::WebhookStage;
+pub use webhook::{ca_bundle, WebhookStage};
 
 pub use utils::{
-    determine_stage, perform_cluster_get, perform_cluster_operation, perform_get,
-    perform_operation, update_status, Operation,
+    apply_operation, clear_creation_failure, determine_stage, patch_status, perform_cluster_get,
+    perform_cluster_operation, perform_get, perform_operation, publish_event, server_side_apply,
+    service_name, serving, set_managed_by, set_owner, spec_changed, stamp_spec_hash,
+    update_failures, update_object_not_owned, update_status, Operation, FIELD_MANAGER,
+    MANAGED_BY_LABEL,
 };
@@ -1,12 +1,14 @@
 use k8s_openapi::api::core::v1::Service;
 use k8s_openapi::api::core::v1::{ServicePort, ServiceSpec};
-use kube::core::ObjectMeta;
-use kube::core::ResourceExt;
+use kube::core::{ObjectMeta, ResourceExt};
 use kube::Client;
 use std::collections::BTreeMap;
+use tracing::info;
 
 use super::perform_get;
 use super::perform_operation;
+use super::service_name;
+use super::{apply_operation, set_managed_by, set_owner, spec_changed, stamp_spec_hash};
 use super::{determine_stage, update_status, Operation};
 use crate::controller::Error;
 use crate::crd::{DeploymentType, Stage, WebhookHelper};
@@ -47,10 +49,14 @@ impl ServiceStage {
             Operation::Delete => {
                 if let Some(status) = self.webhook.status.clone() {
                     if let Some(s) = status.service {
-                        let service: Service =
-                            perform_get(self.client.clone(), &s, &self.webhook.spec.namespace)
-                                .await?;
-                        self.service = Some(service);
+                        match perform_get(self.client.clone(), &s, &self.webhook.spec.namespace)
+                            .await
+                        {
+                            Ok(service) => self.service = Some(service),
+                            // already gone, e.g. garbage collected
+                            Err(kube::Error::Api(e)) if e.code == 404 => return Ok(()),
+                            Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+                        };
                         self.delete().await?;
                     };
                 };
@@ -70,7 +76,9 @@ impl ServiceStage {
                         .await?;
                     }
                     _ => {
-                        // check if operation is update
+                        if let Operation::Update = self.operation {
+                            self.update_service().await?;
+                        };
                     }
                 };
             }
@@ -79,11 +87,57 @@ impl ServiceStage {
     }
 
     async fn create_service(&mut self) -> Result<Service, Error> {
-        let name = match self.deployment.clone().unwrap() {
-            DeploymentType::Deployment(d) => d.name_any(),
-            DeploymentType::Pod(p) => p.name_any(),
+        let service = self.desired_service()?;
+        let result = perform_operation(
+            self.client.clone(),
+            apply_operation(&self.webhook),
+            &service,
+        )
+        .await?;
+        self.service = Some(result.clone());
+        Ok(result)
+    }
+
+    /// Point the live service at the current port and selector, recreating it if it was deleted
+    async fn update_service(&mut self) -> Result<(), Error> {
+        let name = match self.webhook.status.as_ref().and_then(|s| s.service.clone()) {
+            Some(name) => name,
+            None => return Ok(()),
+        };
+        let desired = self.desired_service()?;
+        if desired.name_any() != name {
+            return Err(Error::UnableToCreateObject(
+                "The name of the service cannot be changed".into(),
+            ));
         };
 
+        match perform_get::<Service>(self.client.clone(), &name, &self.webhook.spec.namespace).await
+        {
+            Ok(live) if !spec_changed(&desired, &live) => {
+                self.service = Some(live);
+                return Ok(());
+            }
+            Ok(_) => {}
+            Err(kube::Error::Api(e)) if e.code == 404 => {
+                info!("Service {} was deleted, recreating it", name);
+            }
+            Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+        };
+
+        let result = perform_operation(
+            self.client.clone(),
+            apply_operation(&self.webhook),
+            &desired,
+        )
+        .await?;
+        self.service = Some(result);
+        info!("Service {} updated", name);
+        Ok(())
+    }
+
+    fn desired_service(&self) -> Result<Service, Error> {
+        let name = service_name(&self.webhook)?;
+
         let mut selector_map: BTreeMap<String, String> = BTreeMap::new();
         selector_map.insert("app".to_string(), name.clone());
         selector_map.insert(
@@ -91,7 +145,7 @@ impl ServiceStage {
             "webhook-helper".into(),
         );
 
-        let service = Service {
+        let mut service = Service {
             metadata: ObjectMeta {
                 name: Some(name),
                 namespace: Some(self.webhook.spec.namespace.clone()),
@@ -108,10 +162,10 @@ impl ServiceStage {
             }),
             ..Default::default()
         };
-
-        let result = perform_operation(self.client.clone(), Operation::Create, &service).await?;
-        self.service = Some(result.clone());
-        Ok(result)
+        set_managed_by(&mut service);
+        set_owner(&mut service, &self.webhook);
+        stamp_spec_hash(&mut service)?;
+        Ok(service)
     }
 
     pub async fn get_service(&self) -> Option<Service> {
@@ -3,26 +3,27 @@ use kube::core::ResourceExt;
 use kube::Api;
 use kube::Client;
 
-use serde_json::Value;
-
 use kube::api::Patch;
 use kube::api::PatchParams;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
-use serde_json::json;
+use serde_json::{json, Map, Value};
 use std::fmt::Debug;
 
 use crate::controller::Error;
 use crate::crd::WebhookType;
 use crate::crd::{
     DeploymentType, Stage, WebhookHelper, WebhookHelperCondition, WebhookHelperStatus,
+    BACKEND_READY_CONDITION, CERTIFICATE_READY_CONDITION, COMPONENT_CONDITIONS, READY_CONDITION,
+    SERVICE_READY_CONDITION, WEBHOOK_REGISTERED_CONDITION,
 };
 use chrono::offset::Utc;
-use chrono::DateTime;
+use chrono::{DateTime, SecondsFormat};
 use k8s_openapi::{ClusterResourceScope, NamespaceResourceScope};
+use kube::runtime::events::{Event, EventType, Recorder, Reporter};
 use kube::Resource;
-use std::time::SystemTime;
-use tracing::info;
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
 
 #[derive(Serialize, Deserialize, Clone)]
 pub enum Operation {
@@ -31,31 +32,159 @@ pub enum Operation {
     Update,
     Delete,
     Bootstrap,
-    ApplyOwner(String),
+    /// Server-side apply, taking over fields owned by other managers when set
+    Apply(bool),
     Unknown(String),
 }
 
-async fn apply_owner<T>(api: Api<T>, owner: String, value: &T) -> Result<(), Error>
+/// Field manager every child object is applied under
+pub const FIELD_MANAGER: &str = "webhook-helper";
+
+/// Server-side apply for the helper's children, forced if the helper asks for it
+pub fn apply_operation(webhook: &WebhookHelper) -> Operation {
+    Operation::Apply(webhook.spec.force_apply.unwrap_or(false))
+}
+
+/// Apply `value` under the `webhook-helper` field manager, a conflict with another manager is returned as `ApplyConflict`
+///
+/// An object that already exists under the name is adopted when it is labelled as managed by
+/// webhook-helper or references the helper, otherwise it is refused with `ObjectNotOwned`.
+pub async fn server_side_apply<T>(api: Api<T>, force: bool, value: &T) -> Result<T, Error>
 where
     T: Clone + DeserializeOwned + Debug + Serialize + ResourceExt,
 {
-    let owner_ref: Value = json!({
-        "metadata": {
-            "ownerReferences": [{
-                "apiVersion": "v1",
-                "kind": "WebhookHelper",
-                "name": "webhook-helper.io",
-                "uid": owner,
-                "blockOwnerDeletion": false
-            }]
+    // left over from an attempt that did not get to record it, or someone else's
+    match api.get(&value.name_any()).await {
+        Ok(existing) => {
+            let kind = kind_of(value);
+            if let Some(reason) = foreign_owner(&existing, value) {
+                return Err(Error::ObjectNotOwned(kind, value.name_any(), reason));
+            };
+            if !controlled_by_helper(&existing, value) {
+                info!("Adopting existing {} {}", kind, value.name_any());
+            };
         }
-    });
+        Err(kube::Error::Api(e)) if e.code == 404 => {}
+        Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+    };
 
-    let patch: Patch<&Value> = Patch::Merge(&owner_ref);
-    api.patch(&value.name_any(), &PatchParams::default(), &patch)
-        .await?;
+    let mut params = PatchParams::apply(FIELD_MANAGER);
+    if force {
+        params = params.force();
+    };
+    match api
+        .patch(&value.name_any(), &params, &Patch::Apply(value))
+        .await
+    {
+        Ok(result) => Ok(result),
+        Err(kube::Error::Api(e)) if e.code == 409 => {
+            Err(Error::ApplyConflict(value.name_any(), e.message))
+        }
+        Err(e) => Err(Error::WebhookHelperCreationFailed(e)),
+    }
+}
+
+fn kind_of<T: Serialize>(value: &T) -> String {
+    serde_json::to_value(value)
+        .ok()
+        .and_then(|v| v.get("kind").and_then(|k| k.as_str()).map(String::from))
+        .unwrap_or("Object".into())
+}
+
+/// Uid of the helper a desired child is to be controlled by
+fn helper_uid<T: ResourceExt>(desired: &T) -> Option<String> {
+    desired
+        .owner_references()
+        .iter()
+        .find(|o| o.controller == Some(true))
+        .map(|o| o.uid.clone())
+}
+
+fn controlled_by_helper<T: ResourceExt>(existing: &T, desired: &T) -> bool {
+    let uid = helper_uid(desired);
+    existing
+        .owner_references()
+        .iter()
+        .any(|o| o.controller == Some(true) && Some(&o.uid) == uid.as_ref())
+}
 
-    Ok(())
+/// Why an existing object cannot be taken over as a child, `None` if it can
+fn foreign_owner<T: ResourceExt>(existing: &T, desired: &T) -> Option<String> {
+    let uid = helper_uid(desired);
+    if let Some(owner) = existing
+        .owner_references()
+        .iter()
+        .find(|o| o.controller == Some(true))
+    {
+        if Some(&owner.uid) != uid.as_ref() {
+            return Some(format!("controlled by {} {}", owner.kind, owner.name));
+        };
+        return None;
+    };
+    let managed =
+        existing.labels().get(MANAGED_BY_LABEL).map(String::as_str) == Some(FIELD_MANAGER);
+    // children from before the label was set only reference the helper
+    let referenced = existing
+        .owner_references()
+        .iter()
+        .any(|o| Some(&o.uid) == uid.as_ref());
+    match managed || referenced {
+        true => None,
+        false => Some(format!(
+            "exists without the {}={} label",
+            MANAGED_BY_LABEL, FIELD_MANAGER
+        )),
+    }
+}
+
+/// Label carried by every child, the controller only watches labelled objects
+pub const MANAGED_BY_LABEL: &str = "app.kubernetes.io/managed-by";
+
+/// Mark a child as managed by webhook-helper
+pub fn set_managed_by<T: ResourceExt>(resource: &mut T) {
+    resource
+        .labels_mut()
+        .insert(MANAGED_BY_LABEL.into(), FIELD_MANAGER.into());
+}
+
+/// Make the helper the controlling owner of a child, so garbage collection removes it with the helper
+pub fn set_owner<T: ResourceExt>(resource: &mut T, webhook: &WebhookHelper) {
+    if let Some(owner) = webhook.controller_owner_ref(&()) {
+        resource.meta_mut().owner_references = Some(vec![owner]);
+    };
+}
+
+/// Annotation holding the hash of the object webhook-helper last wanted a child to be
+pub const SPEC_HASH_ANNOTATION: &str = "webhook-helper.io/spec-hash";
+
+/// Hash a desired child and record it on the child, so a later reconcile can tell whether the spec changed
+///
+/// Comparing against the live object directly would trip over fields the API server defaults.
+pub fn stamp_spec_hash<T>(resource: &mut T) -> Result<String, Error>
+where
+    T: Serialize + ResourceExt,
+{
+    resource.annotations_mut().remove(SPEC_HASH_ANNOTATION);
+    let hash = format!("{:x}", Sha256::digest(serde_json::to_vec(resource)?));
+    resource
+        .annotations_mut()
+        .insert(SPEC_HASH_ANNOTATION.into(), hash.clone());
+    Ok(hash)
+}
+
+/// Whether `live` was created from a different desired object than `desired`
+pub fn spec_changed<T: ResourceExt>(desired: &T, live: &T) -> bool {
+    desired.annotations().get(SPEC_HASH_ANNOTATION) != live.annotations().get(SPEC_HASH_ANNOTATION)
+}
+
+/// Name of the service fronting the helper's deployment or pod
+pub fn service_name(webhook: &WebhookHelper) -> Result<String, Error> {
+    match webhook.spec.deployment["metadata"]["name"].as_str() {
+        Some(name) => Ok(name.to_string()),
+        None => Err(Error::UnableToCreateObject(
+            "No metadata.name found in deployment".into(),
+        )),
+    }
 }
 
 pub async fn perform_operation<T>(
@@ -89,11 +218,8 @@ where
             let result = api.replace(&value.name_any(), &pp, value).await?;
             Ok(result)
         }
+        Operation::Apply(force) => server_side_apply(api, force, value).await,
         Operation::Unknown(op) => Err(Error::UnknownOperation(op)),
-        Operation::ApplyOwner(owner) => {
-            apply_owner(api, owner, value).await?;
-            Ok(value.clone())
-        }
         Operation::Bootstrap => Err(Error::UnknownOperation("Bootstrap".into())),
     }
 }
@@ -140,11 +266,8 @@ where
             let result = api.replace(&value.name_any(), &pp, value).await?;
             Ok(result)
         }
+        Operation::Apply(force) => server_side_apply(api, force, value).await,
         Operation::Unknown(op) => Err(Error::UnknownOperation(op)),
-        Operation::ApplyOwner(owner) => {
-            apply_owner(api, owner, value).await?;
-            Ok(value.clone())
-        }
         Operation::Bootstrap => Err(Error::UnknownOperation("Bootstrap".into())),
     }
 }
@@ -166,27 +289,66 @@ pub async fn update_status(
     resource: WebhookHelper,
 ) -> Result<WebhookHelper, Error> {
     info!("Updating Status");
-    let pp = PostParams::default();
-    let api: Api<WebhookHelper> = Api::all(client.clone());
-
-    let mut result = api.get_status(&resource.name_any()).await?;
+    let generation = resource.meta().generation;
+    let rotated = matches!(stage, Stage::CertificateRotated(_));
+    let (event_type, action) = stage_event(&stage);
+    let (event_reason, note) = (stage.to_string(), stage.message());
+    let mut transition = false;
 
-    let mut status = match result.status {
-        Some(s) => s,
-        None => WebhookHelperStatus::default(),
+    let updated = patch_status(client.clone(), &resource, |status| {
+        // every rotation is recorded, other stages only when they are reached
+        transition = rotated || status.stage != Some(stage.to_string());
+        // a rotation happens while the helper stays at its stage
+        if !rotated {
+            status.stage = Some(stage.to_string());
+            // a renewal keeps serving the mounted certificate
+            if !renewing(status, &stage) {
+                status.phase = Some(stage.phase().into());
+            };
+        };
+        apply_stage(status, stage, generation);
+    })
+    .await?;
+    if transition {
+        publish_event(client, &resource, event_type, &event_reason, action, note).await;
     };
+    Ok(updated)
+}
 
-    let datetime: DateTime<Utc> = SystemTime::now().into();
-    let mut condition_entry = WebhookHelperCondition {
-        type__: format!("{}", stage),
-        message: stage.message(),
-        status: "True".into(),
-        last_transition_time: format!("{}", datetime.format("%d/%m/%Y %T")),
-    };
+/// Forget earlier creation failures once a certificate was issued
+pub fn clear_creation_failure(status: &mut WebhookHelperStatus) {
+    status.creation_retries = None;
+    status.creation_failed_at = None;
+}
+
+/// Whether the helper already serves a webhook, so issuing a certificate renews it
+pub fn serving(status: &WebhookHelperStatus) -> bool {
+    status.validating_webhook.is_some() || status.mutating_webhook.is_some()
+}
+
+/// Whether `stage` is a step of issuing a certificate for a helper that is already serving
+fn renewing(status: &WebhookHelperStatus, stage: &Stage) -> bool {
+    let issuing = matches!(
+        stage,
+        Stage::CsrCreated(_)
+            | Stage::CsrApproved(_)
+            | Stage::CertificateIssued(_)
+            | Stage::CertificateRequested(_)
+    );
+    issuing && serving(status)
+}
 
+/// Record what reaching `stage` means for the status fields and conditions
+fn apply_stage(status: &mut WebhookHelperStatus, stage: Stage, generation: Option<i64>) {
+    let condition = stage_condition(&stage, renewing(status, &stage));
+    let deleting = matches!(stage, Stage::Deleting);
     match stage {
+        Stage::CertificateFailed(_, _) => {
+            status.failed_generation = generation;
+        }
         Stage::CreationFailed(_) => {
-            condition_entry.status = "False".into();
+            status.failed_generation = generation;
+            status.creation_failed_at = Some(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true));
         }
         Stage::DeploymentComplete(dep) | Stage::DeploymentStarted(dep) => {
             match dep {
@@ -208,8 +370,18 @@ pub async fn update_status(
                 }
             };
         }
+        Stage::CsrCreated(c)
+        | Stage::CsrApproved(c)
+        | Stage::CertificateIssued(c)
+        | Stage::CertificateRequested(c) => {
+            status.certificate_request = Some(c);
+        }
         Stage::CertificateCreated(c) => {
             status.certificate = Some(c);
+            clear_creation_failure(status);
+        }
+        Stage::CertificateRotated(r) => {
+            status.last_rotation = Some(r);
         }
         Stage::ServiceCreated(s) => {
             status.service = Some(s.name_any());
@@ -217,101 +389,519 @@ pub async fn update_status(
         _ => {}
     };
 
-    if let Some(mut v) = status.conditions.clone() {
-        v.push(condition_entry);
-        status.conditions = Some(v);
-    } else {
-        status.conditions = Some(vec![condition_entry]);
+    // entries from before the fixed set of conditions are dropped
+    let mut conditions: Vec<WebhookHelperCondition> = status
+        .conditions
+        .take()
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|c| {
+            c.type__ == READY_CONDITION || COMPONENT_CONDITIONS.contains(&c.type__.as_str())
+        })
+        .collect();
+    for type_ in COMPONENT_CONDITIONS {
+        if !conditions.iter().any(|c| c.type__ == type_) {
+            set_condition(
+                &mut conditions,
+                type_,
+                "Unknown",
+                "Pending".into(),
+                "Not reached yet".into(),
+                generation,
+            );
+        };
+    }
+    if let Some((type_, condition_status, reason, message)) = condition {
+        set_condition(
+            &mut conditions,
+            type_,
+            condition_status,
+            reason,
+            message,
+            generation,
+        );
+    };
+
+    set_ready(&mut conditions, deleting, generation);
+    status.conditions = Some(conditions);
+}
+
+/// Change the helper's status with `update`, writing only the fields it changed as a merge patch
+///
+/// Fields left alone are not sent, so writers touching different fields cannot undo each other.
+pub async fn patch_status<F>(
+    client: Client,
+    resource: &WebhookHelper,
+    update: F,
+) -> Result<WebhookHelper, Error>
+where
+    F: FnOnce(&mut WebhookHelperStatus),
+{
+    let api: Api<WebhookHelper> = Api::all(client);
+    let current = api.get_status(&resource.name_any()).await?;
+    let before = current.status.clone().unwrap_or_default();
+    let mut status = before.clone();
+    update(&mut status);
+
+    let changed = changed_fields(
+        serde_json::to_value(&before)?,
+        serde_json::to_value(&status)?,
+    );
+    if changed.is_empty() {
+        return Ok(current);
+    };
+    let params = PatchParams {
+        field_manager: Some(FIELD_MANAGER.into()),
+        ..Default::default()
+    };
+    let patch = json!({ "status": changed });
+    let updated = api
+        .patch_status(&resource.name_any(), &params, &Patch::Merge(&patch))
+        .await?;
+    Ok(updated)
+}
+
+/// Top level fields of `after` that differ from `before`, unset fields are `null` so the patch clears them
+fn changed_fields(before: Value, after: Value) -> Map<String, Value> {
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => after
+            .into_iter()
+            .filter(|(key, value)| before.get(key) != Some(value))
+            .collect(),
+        _ => Map::new(),
+    }
+}
+
+/// Type and action of the event recorded when a stage is reached
+fn stage_event(stage: &Stage) -> (EventType, &'static str) {
+    match stage {
+        Stage::HelperCreated => (EventType::Normal, "Create"),
+        Stage::CsrCreated(_)
+        | Stage::CsrApproved(_)
+        | Stage::CertificateIssued(_)
+        | Stage::CertificateRequested(_)
+        | Stage::CertificateCreated(_) => (EventType::Normal, "IssueCertificate"),
+        Stage::CertificateRotated(_) => (EventType::Normal, "RotateCertificate"),
+        Stage::CertificateFailed(_, _) | Stage::CreationFailed(_) => {
+            (EventType::Warning, "IssueCertificate")
+        }
+        Stage::DeploymentStarted(_) | Stage::DeploymentComplete(_) => (EventType::Normal, "Deploy"),
+        Stage::ServiceCreated(_) => (EventType::Normal, "CreateService"),
+        Stage::WebhookCreated(_) => (EventType::Normal, "RegisterWebhook"),
+        Stage::Deleting => (EventType::Normal, "Delete"),
+    }
+}
+
+/// Condition a stage reports on, with its status, reason and message
+fn stage_condition(
+    stage: &Stage,
+    renewing: bool,
+) -> Option<(&'static str, &'static str, String, String)> {
+    let (type_, condition_status) = match stage {
+        Stage::HelperCreated | Stage::Deleting => return None,
+        // the mounted certificate stays valid while its successor is issued
+        _ if renewing => {
+            return Some((
+                CERTIFICATE_READY_CONDITION,
+                "True",
+                "Renewing".into(),
+                stage.message(),
+            ))
+        }
+        Stage::CertificateFailed(reason, message) => {
+            return Some((
+                CERTIFICATE_READY_CONDITION,
+                "False",
+                reason.clone(),
+                message.clone(),
+            ))
+        }
+        Stage::CreationFailed(message) => {
+            return Some((
+                CERTIFICATE_READY_CONDITION,
+                "False",
+                stage.to_string(),
+                message.clone(),
+            ))
+        }
+        Stage::CsrCreated(_)
+        | Stage::CsrApproved(_)
+        | Stage::CertificateIssued(_)
+        | Stage::CertificateRequested(_) => (CERTIFICATE_READY_CONDITION, "False"),
+        Stage::CertificateCreated(_) | Stage::CertificateRotated(_) => {
+            (CERTIFICATE_READY_CONDITION, "True")
+        }
+        Stage::DeploymentStarted(_) => (BACKEND_READY_CONDITION, "False"),
+        Stage::DeploymentComplete(_) => (BACKEND_READY_CONDITION, "True"),
+        Stage::ServiceCreated(_) => (SERVICE_READY_CONDITION, "True"),
+        Stage::WebhookCreated(_) => (WEBHOOK_REGISTERED_CONDITION, "True"),
     };
+    Some((type_, condition_status, stage.to_string(), stage.message()))
+}
 
-    result.status = Some(status);
+/// `Ready` follows the first component condition that is not `True`
+fn set_ready(
+    conditions: &mut Vec<WebhookHelperCondition>,
+    deleting: bool,
+    generation: Option<i64>,
+) {
+    let pending = COMPONENT_CONDITIONS.iter().find_map(|type_| {
+        conditions
+            .iter()
+            .find(|c| c.type__ == *type_ && c.status != "True")
+            .cloned()
+    });
+    let (ready, reason, message) = match pending {
+        _ if deleting => ("False", "Deleting".into(), "Deleting resource".into()),
+        Some(c) => ("False", c.reason.unwrap_or_default(), c.message),
+        None => ("True", "WebhookServing".into(), "Webhook is served".into()),
+    };
+    set_condition(
+        conditions,
+        READY_CONDITION,
+        ready,
+        reason,
+        message,
+        generation,
+    );
+}
 
-    let data: Vec<u8> = serde_json::to_vec(&result)?;
+/// Mark the condition of a child's component `ObjectNotOwned` when its object belongs to something else
+pub async fn update_object_not_owned(
+    client: Client,
+    kind: &str,
+    message: String,
+    resource: WebhookHelper,
+) -> Result<WebhookHelper, Error> {
+    let type_ = match kind {
+        "Deployment" | "Pod" => BACKEND_READY_CONDITION,
+        "Service" => SERVICE_READY_CONDITION,
+        "ValidatingWebhookConfiguration" | "MutatingWebhookConfiguration" => {
+            WEBHOOK_REGISTERED_CONDITION
+        }
+        _ => CERTIFICATE_READY_CONDITION,
+    };
+    let generation = resource.meta().generation;
+    patch_status(client, &resource, |status| {
+        let mut conditions = status.conditions.take().unwrap_or_default();
+        set_condition(
+            &mut conditions,
+            type_,
+            "False",
+            "ObjectNotOwned".into(),
+            message,
+            generation,
+        );
+        set_ready(&mut conditions, false, generation);
+        status.conditions = Some(conditions);
+    })
+    .await
+}
 
-    let updated_status = api.replace_status(&resource.name_any(), &pp, data).await?;
-    // info!("Status Updated: {:?}", updated_status);
-    Ok(updated_status)
+/// Update a condition in place, moving `lastTransitionTime` only when its status changes
+fn set_condition(
+    conditions: &mut Vec<WebhookHelperCondition>,
+    type_: &str,
+    condition_status: &str,
+    reason: String,
+    message: String,
+    generation: Option<i64>,
+) {
+    let now = Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true);
+    match conditions.iter_mut().find(|c| c.type__ == type_) {
+        Some(c) => {
+            if c.status != condition_status {
+                c.status = condition_status.into();
+                c.last_transition_time = now;
+            };
+            c.reason = Some(reason);
+            c.message = message;
+            c.observed_generation = generation;
+        }
+        None => conditions.push(WebhookHelperCondition {
+            type__: type_.into(),
+            reason: Some(reason),
+            message,
+            status: condition_status.into(),
+            last_transition_time: now,
+            observed_generation: generation,
+        }),
+    };
+}
+
+/// Record a failed reconcile and when to retry it, `None` once one succeeds
+pub async fn update_failures(
+    client: Client,
+    failure: Option<(u32, String, DateTime<Utc>)>,
+    resource: WebhookHelper,
+) -> Result<WebhookHelper, Error> {
+    let generation = resource.meta().generation;
+    patch_status(client, &resource, |status| match failure {
+        Some((failures, last_error, retry_after)) => {
+            status.failures = Some(failures);
+            status.last_error = Some(last_error);
+            status.retry_after = Some(retry_after.to_rfc3339_opts(SecondsFormat::Secs, true));
+            status.last_error_generation = generation;
+        }
+        None => {
+            status.failures = None;
+            status.last_error = None;
+            status.last_error_generation = None;
+            status.retry_after = None;
+        }
+    })
+    .await
+}
+
+/// Record a Kubernetes event against the helper, failures are only logged
+pub async fn publish_event(
+    client: Client,
+    resource: &WebhookHelper,
+    type_: EventType,
+    reason: &str,
+    action: &str,
+    note: String,
+) {
+    let reporter = Reporter {
+        controller: "webhook-helper".into(),
+        instance: None,
+    };
+    let recorder = Recorder::new(client, reporter, resource.object_ref(&()));
+    if let Err(e) = recorder
+        .publish(Event {
+            type_,
+            reason: reason.into(),
+            note: Some(note),
+            action: action.into(),
+            secondary: None,
+        })
+        .await
+    {
+        warn!("Unable to publish event for {}: {}", resource.name_any(), e);
+    };
 }
 
 pub async fn determine_stage(client: Client, value: WebhookHelper) -> Result<Stage, Error> {
     let api: Api<WebhookHelper> = Api::all(client.clone());
     let result = api.get_status(&value.name_any()).await?;
     let namespace = &value.spec.namespace;
-    match result.status {
-        Some(status) => {
-            if let Some(conditions) = status.conditions {
-                if let Some(last) = conditions.last() {
-                    let result = match last.type__.as_str() {
-                        "WebhookHelperCreated" => Ok(Stage::HelperCreated),
-                        "CertificateCreated" => Ok(Stage::CertificateCreated(
-                            status.certificate.unwrap_or("<unknown>".into()),
-                        )),
-                        "DeploymentComplete" => {
-                            if let Some(deployment) = status.deployment {
-                                let dep =
-                                    perform_get(client.clone(), &deployment, namespace).await?;
-                                Ok(Stage::DeploymentComplete(DeploymentType::Deployment(dep)))
-                            } else if let Some(pod) = status.pod {
-                                let pod = perform_get(client.clone(), &pod, namespace).await?;
-                                Ok(Stage::DeploymentComplete(DeploymentType::Pod(pod)))
-                            } else {
-                                Err(Error::UnknownOperation(
-                                    "Unable to determine deployment type".into(),
-                                ))
-                            }
-                        }
-                        "DeploymentStarted" => {
-                            if let Some(deployment) = status.deployment {
-                                let dep =
-                                    perform_get(client.clone(), &deployment, namespace).await?;
-                                Ok(Stage::DeploymentStarted(DeploymentType::Deployment(dep)))
-                            } else if let Some(pod) = status.pod {
-                                let pod = perform_get(client.clone(), &pod, namespace).await?;
-                                Ok(Stage::DeploymentStarted(DeploymentType::Pod(pod)))
-                            } else {
-                                Err(Error::UnknownOperation(
-                                    "Unable to determine deployment type".into(),
-                                ))
-                            }
-                        }
-                        "ServiceCreated" => {
-                            if let Some(service) = status.service {
-                                let result =
-                                    perform_get(client.clone(), &service, namespace).await?;
-                                Ok(Stage::ServiceCreated(result))
-                            } else {
-                                Err(Error::UnknownOperation("Unable to get service".into()))
-                            }
-                        }
-                        "WebhookCreated" => {
-                            if let Some(webhook) = status.mutating_webhook {
-                                let mut_webhook =
-                                    perform_cluster_get(client.clone(), &webhook).await?;
-                                Ok(Stage::WebhookCreated(WebhookType::Mutating(mut_webhook)))
-                            } else if let Some(webhook) = status.validating_webhook {
-                                let val_webhook =
-                                    perform_cluster_get(client.clone(), &webhook).await?;
-                                Ok(Stage::WebhookCreated(WebhookType::Validating(val_webhook)))
-                            } else {
-                                Err(Error::UnknownOperation(
-                                    "Unable to determine webhook type".into(),
-                                ))
-                            }
-                        }
-                        "CreationFailed" => Ok(Stage::CreationFailed(last.message.clone())),
-                        _ => Err(Error::UnknownOperation(format!(
-                            "Unable to determine condition type: {}",
-                            last.type__
-                        ))),
-                    };
-                    result
-                } else {
-                    Ok(Stage::HelperCreated)
-                }
+    let status = match result.status {
+        Some(status) => status,
+        None => return Ok(Stage::HelperCreated),
+    };
+
+    let conditions = status.conditions.clone().unwrap_or_default();
+    let stage = match status.stage.clone() {
+        Some(stage) => stage,
+        // helpers from before `stage` was recorded kept it as the last condition
+        None => match conditions.last() {
+            Some(last) => last.type__.clone(),
+            None => return Ok(Stage::HelperCreated),
+        },
+    };
+    // failures carry their reason and message on the certificate condition
+    let detail = conditions
+        .iter()
+        .find(|c| c.type__ == CERTIFICATE_READY_CONDITION)
+        .or(conditions.last())
+        .cloned()
+        .unwrap_or_default();
+
+    match stage.as_str() {
+        "WebhookHelperCreated" => Ok(Stage::HelperCreated),
+        "CsrCreated" => Ok(Stage::CsrCreated(
+            status.certificate_request.unwrap_or("<unknown>".into()),
+        )),
+        "CsrApproved" => Ok(Stage::CsrApproved(
+            status.certificate_request.unwrap_or("<unknown>".into()),
+        )),
+        "CertificateIssued" => Ok(Stage::CertificateIssued(
+            status.certificate_request.unwrap_or("<unknown>".into()),
+        )),
+        "CertificateRequested" => Ok(Stage::CertificateRequested(
+            status.certificate_request.unwrap_or("<unknown>".into()),
+        )),
+        "CertificateFailed" => Ok(Stage::CertificateFailed(
+            detail.reason.unwrap_or_default(),
+            detail.message,
+        )),
+        "CertificateCreated" => Ok(certificate_created(&status)),
+        "DeploymentComplete" => deployment_complete(client, &status, namespace).await,
+        "DeploymentStarted" => match recorded_deployment(client, &status, namespace).await? {
+            Some(deployment) => Ok(Stage::DeploymentStarted(deployment)),
+            // started again from the certificate
+            None => Ok(certificate_created(&status)),
+        },
+        "ServiceCreated" => service_created(client, &status, namespace).await,
+        // a rotation leaves the webhook as it was
+        "WebhookCreated" | "CertificateRotated" => {
+            let webhook = if let Some(webhook) = status.mutating_webhook.as_ref() {
+                or_deleted(perform_cluster_get(client.clone(), webhook).await)?
+                    .map(WebhookType::Mutating)
+            } else if let Some(webhook) = status.validating_webhook.as_ref() {
+                or_deleted(perform_cluster_get(client.clone(), webhook).await)?
+                    .map(WebhookType::Validating)
             } else {
-                Ok(Stage::HelperCreated)
+                return Err(Error::UnknownOperation(
+                    "Unable to determine webhook type".into(),
+                ));
+            };
+            match webhook {
+                Some(webhook) => Ok(Stage::WebhookCreated(webhook)),
+                // registered again from the service
+                None => service_created(client, &status, namespace).await,
             }
         }
-        None => Ok(Stage::HelperCreated),
+        "CreationFailed" => Ok(Stage::CreationFailed(detail.message)),
+        "Deleting" => Ok(Stage::Deleting),
+        _ => Err(Error::UnknownOperation(format!(
+            "Unable to determine stage: {}",
+            stage
+        ))),
+    }
+}
+
+/// A child recorded in status, `None` once it was deleted
+fn or_deleted<T>(result: Result<T, kube::Error>) -> Result<Option<T>, Error> {
+    match result {
+        Ok(child) => Ok(Some(child)),
+        Err(kube::Error::Api(e)) if e.code == 404 => Ok(None),
+        Err(e) => Err(Error::WebhookHelperCreationFailed(e)),
+    }
+}
+
+/// The Deployment or Pod recorded in status, `None` once it was deleted
+async fn recorded_deployment(
+    client: Client,
+    status: &WebhookHelperStatus,
+    namespace: &str,
+) -> Result<Option<DeploymentType>, Error> {
+    if let Some(deployment) = status.deployment.as_ref() {
+        Ok(
+            or_deleted(perform_get(client, deployment, namespace).await)?
+                .map(DeploymentType::Deployment),
+        )
+    } else if let Some(pod) = status.pod.as_ref() {
+        Ok(or_deleted(perform_get(client, pod, namespace).await)?.map(DeploymentType::Pod))
+    } else {
+        Err(Error::UnknownOperation(
+            "Unable to determine deployment type".into(),
+        ))
+    }
+}
+
+/// `CertificateCreated` for the secret recorded in status
+fn certificate_created(status: &WebhookHelperStatus) -> Stage {
+    Stage::CertificateCreated(status.certificate.clone().unwrap_or("<unknown>".into()))
+}
+
+/// `DeploymentComplete`, or back to `CertificateCreated` so the backend is created again once it was deleted
+async fn deployment_complete(
+    client: Client,
+    status: &WebhookHelperStatus,
+    namespace: &str,
+) -> Result<Stage, Error> {
+    match recorded_deployment(client, status, namespace).await? {
+        Some(deployment) => Ok(Stage::DeploymentComplete(deployment)),
+        None => Ok(certificate_created(status)),
+    }
+}
+
+/// `ServiceCreated`, or back to the stage before it so the service is created again once it was deleted
+async fn service_created(
+    client: Client,
+    status: &WebhookHelperStatus,
+    namespace: &str,
+) -> Result<Stage, Error> {
+    let service = match status.service.as_ref() {
+        Some(service) => service,
+        None => return Err(Error::UnknownOperation("Unable to get service".into())),
+    };
+    match or_deleted(perform_get(client.clone(), service, namespace).await)? {
+        Some(service) => Ok(Stage::ServiceCreated(service)),
+        None => deployment_complete(client, status, namespace).await,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use k8s_openapi::api::admissionregistration::v1::ValidatingWebhookConfiguration;
+    use k8s_openapi::api::apps::v1::Deployment;
+    use k8s_openapi::api::core::v1::Service;
+
+    fn condition<'a>(status: &'a WebhookHelperStatus, type_: &str) -> &'a WebhookHelperCondition {
+        status
+            .conditions
+            .as_ref()
+            .and_then(|c| c.iter().find(|c| c.type__ == type_))
+            .unwrap()
+    }
+
+    /// A helper that went through every stage and serves its webhook
+    fn serving_status() -> WebhookHelperStatus {
+        let mut status = WebhookHelperStatus::default();
+        let mut webhook = ValidatingWebhookConfiguration::default();
+        webhook.metadata.name = Some("webhook".into());
+        for stage in [
+            Stage::CertificateCreated("secret".into()),
+            Stage::DeploymentComplete(DeploymentType::Deployment(Deployment::default())),
+            Stage::ServiceCreated(Service::default()),
+            Stage::WebhookCreated(WebhookType::Validating(webhook)),
+        ] {
+            apply_stage(&mut status, stage, Some(1));
+        }
+        status
+    }
+
+    #[test]
+    fn ready_once_every_component_is() {
+        let status = serving_status();
+        assert_eq!(condition(&status, READY_CONDITION).status, "True");
+        for type_ in COMPONENT_CONDITIONS {
+            assert_eq!(condition(&status, type_).status, "True");
+        }
+    }
+
+    #[test]
+    fn issuing_a_first_certificate_is_not_ready() {
+        let mut status = WebhookHelperStatus::default();
+        apply_stage(&mut status, Stage::CsrCreated("csr".into()), Some(1));
+        let certificate = condition(&status, CERTIFICATE_READY_CONDITION);
+        assert_eq!(certificate.status, "False");
+        assert_eq!(certificate.reason.as_deref(), Some("CsrCreated"));
+        assert_eq!(condition(&status, READY_CONDITION).status, "False");
+    }
+
+    #[test]
+    fn renewing_keeps_the_certificate_ready() {
+        let mut status = serving_status();
+        apply_stage(&mut status, Stage::CsrCreated("csr".into()), Some(1));
+        let certificate = condition(&status, CERTIFICATE_READY_CONDITION);
+        assert_eq!(certificate.status, "True");
+        assert_eq!(certificate.reason.as_deref(), Some("Renewing"));
+        assert_eq!(condition(&status, READY_CONDITION).status, "True");
+    }
+
+    #[test]
+    fn transition_time_only_moves_with_the_status() {
+        let mut conditions = Vec::new();
+        let set = |conditions: &mut Vec<WebhookHelperCondition>, status: &str| {
+            set_condition(
+                conditions,
+                READY_CONDITION,
+                status,
+                "Reason".into(),
+                "message".into(),
+                Some(1),
+            )
+        };
+        set(&mut conditions, "False");
+        conditions[0].last_transition_time = "earlier".into();
+        set(&mut conditions, "False");
+        assert_eq!(conditions[0].last_transition_time, "earlier");
+        set(&mut conditions, "True");
+        assert_ne!(conditions[0].last_transition_time, "earlier");
+        assert_eq!(conditions.len(), 1);
     }
 }
@@ -1,18 +1,20 @@
-use k8s_openapi::api::admissionregistration::v1::ServiceReference;
 use k8s_openapi::api::admissionregistration::v1::{
     MutatingWebhookConfiguration, ValidatingWebhookConfiguration,
 };
-use k8s_openapi::api::core::v1::ConfigMap;
+use k8s_openapi::api::admissionregistration::v1::{ServiceReference, WebhookClientConfig};
+use k8s_openapi::api::core::v1::Secret;
 use k8s_openapi::api::core::v1::Service;
 use k8s_openapi::ByteString;
 use kube::core::ResourceExt;
 use kube::Client;
 
 use serde_json::Value;
+use tracing::info;
 
 use super::{
-    determine_stage, perform_cluster_get, perform_cluster_operation, perform_get, update_status,
-    Operation,
+    apply_operation, cluster_ca, determine_stage, perform_cluster_get, perform_cluster_operation,
+    perform_get, provided_ca, service_name, set_managed_by, set_owner, spec_changed,
+    stamp_spec_hash, update_status, Operation,
 };
 use crate::controller::Error;
 use crate::crd::{Stage, WebhookHelper, WebhookType};
@@ -52,17 +54,23 @@ impl WebhookStage {
             }
             Operation::Delete => {
                 if let Some(status) = self.webhook.status.clone() {
-                    if let Some(webhook) = status.validating_webhook {
-                        let hook: ValidatingWebhookConfiguration =
-                            perform_cluster_get(self.client.clone(), &webhook).await?;
-                        self.webhook_resource = Some(WebhookType::Validating(hook));
+                    let result = if let Some(webhook) = status.validating_webhook {
+                        perform_cluster_get(self.client.clone(), &webhook)
+                            .await
+                            .map(WebhookType::Validating)
                     } else if let Some(webhook) = status.mutating_webhook {
-                        let hook: MutatingWebhookConfiguration =
-                            perform_cluster_get(self.client.clone(), &webhook).await?;
-                        self.webhook_resource = Some(WebhookType::Mutating(hook));
+                        perform_cluster_get(self.client.clone(), &webhook)
+                            .await
+                            .map(WebhookType::Mutating)
                     } else {
                         return Ok(());
                     };
+                    match result {
+                        Ok(hook) => self.webhook_resource = Some(hook),
+                        // already gone, e.g. garbage collected
+                        Err(kube::Error::Api(e)) if e.code == 404 => return Ok(()),
+                        Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+                    };
                     self.delete().await?;
                     return Ok(());
                 }
@@ -79,30 +87,11 @@ impl WebhookStage {
                             self.webhook.clone(),
                         )
                         .await?;
-
-                        if let Some(uid) = self.webhook.uid() {
-                            match webhook {
-                                WebhookType::Mutating(d) => {
-                                    perform_cluster_operation(
-                                        self.client.clone(),
-                                        Operation::ApplyOwner(uid),
-                                        &d,
-                                    )
-                                    .await?;
-                                }
-                                WebhookType::Validating(p) => {
-                                    perform_cluster_operation(
-                                        self.client.clone(),
-                                        Operation::ApplyOwner(uid),
-                                        &p,
-                                    )
-                                    .await?;
-                                }
-                            };
-                        };
                     }
                     _ => {
-                        // check if operation is update
+                        if let Operation::Update = self.operation {
+                            self.update_webhook().await?;
+                        };
                     }
                 };
             }
@@ -110,87 +99,153 @@ impl WebhookStage {
         Ok(())
     }
 
+    async fn ca_bundle(&self) -> Result<String, Error> {
+        ca_bundle(self.client.clone(), &self.webhook).await
+    }
+
+    /// Point an existing webhook configuration at the current CA, if it changed
+    pub async fn refresh_ca_bundle(&mut self) -> Result<(), Error> {
+        self.reapply(true).await
+    }
+
     async fn create_webhook(&mut self) -> Result<WebhookType, Error> {
-        let config_map: ConfigMap =
-            perform_get(self.client.clone(), "kube-root-ca.crt", "default").await?;
-        let cluster_ca_string = match config_map.data {
-            Some(cm) => match cm.get("ca.crt") {
-                Some(c) => c.clone(),
-                None => {
-                    return Err(Error::UnableToCreateObject(
-                        "Cound not find signing CA".into(),
-                    ))
-                }
-            },
-            None => {
+        let desired = self.desired_webhook().await?;
+        let webhook = self.apply_webhook(desired).await?;
+        self.webhook_resource = Some(webhook.clone());
+        Ok(webhook)
+    }
+
+    /// Re-apply the spec to the live webhook configuration when it changed
+    async fn update_webhook(&mut self) -> Result<(), Error> {
+        self.reapply(false).await
+    }
+
+    /// Apply the desired webhook configuration over the live one when its CA, or with `ca_only` unset its spec, is stale
+    ///
+    /// A deleted configuration is registered again.
+    async fn reapply(&mut self, ca_only: bool) -> Result<(), Error> {
+        let status = self.webhook.status.clone().unwrap_or_default();
+        let desired = self.desired_webhook().await?;
+        let live = match (&desired, status.validating_webhook, status.mutating_webhook) {
+            (WebhookType::Validating(d), Some(name), _) if d.name_any() == name => {
+                perform_cluster_get(self.client.clone(), &name)
+                    .await
+                    .map(WebhookType::Validating)
+            }
+            (WebhookType::Mutating(d), None, Some(name)) if d.name_any() == name => {
+                perform_cluster_get(self.client.clone(), &name)
+                    .await
+                    .map(WebhookType::Mutating)
+            }
+            (_, None, None) => return Ok(()),
+            _ => {
                 return Err(Error::UnableToCreateObject(
-                    "Cound not find signing CA".into(),
+                    "The kind and name of the webhook cannot be changed".into(),
                 ))
             }
         };
 
-        if let Some(service) = self.service.clone() {
-            if let Ok(mut hook) =
-                convert_to_mutating_webhook(self.webhook.spec.webhook.clone()).await
-            {
-                let current_webhooks = hook
-                    .webhooks
-                    .unwrap_or(Vec::new())
-                    .iter()
-                    .map(|w| {
-                        let mut nwh = w.clone();
-                        nwh.client_config.url = None;
-                        nwh.client_config.ca_bundle =
-                            Some(ByteString(cluster_ca_string.as_bytes().into()));
-                        nwh.client_config.service = Some(ServiceReference {
-                            name: service.name_any(),
-                            namespace: self.webhook.namespace().unwrap_or("default".into()),
-                            port: Some(self.webhook.spec.listening_port),
-                            path: None,
-                        });
-                        nwh
-                    })
-                    .collect();
-                hook.webhooks = Some(current_webhooks);
-
-                let result =
-                    perform_cluster_operation(self.client.clone(), Operation::Create, &hook)
-                        .await?;
-                self.webhook_resource = Some(WebhookType::Mutating(result.clone()));
-                Ok(WebhookType::Mutating(result))
-            } else if let Ok(mut hook) =
-                convert_to_admission_webhook(self.webhook.spec.webhook.clone()).await
-            {
-                let current_webhooks = hook
-                    .webhooks
-                    .unwrap_or(Vec::new())
-                    .iter()
-                    .map(|w| {
-                        let mut nwh = w.clone();
-                        nwh.client_config.url = None;
-                        nwh.client_config.ca_bundle =
-                            Some(ByteString(cluster_ca_string.as_bytes().into()));
-                        nwh.client_config.service = Some(ServiceReference {
-                            name: service.name_any(),
-                            namespace: service.namespace().unwrap_or("default".into()),
-                            port: Some(self.webhook.spec.listening_port),
-                            path: self.webhook.spec.path.clone(),
-                        });
-                        nwh
-                    })
-                    .collect();
-                hook.webhooks = Some(current_webhooks);
-
-                let result =
-                    perform_cluster_operation(self.client.clone(), Operation::Create, &hook)
-                        .await?;
-                self.webhook_resource = Some(WebhookType::Validating(result.clone()));
-                Ok(WebhookType::Validating(result))
-            } else {
-                Err(Error::UnknownOperation(
-                    "Unable to determine webhook type".into(),
-                ))
+        let live = match live {
+            Ok(live) => live,
+            Err(kube::Error::Api(e)) if e.code == 404 => {
+                let webhook = self.apply_webhook(desired).await?;
+                info!(
+                    "Webhook configuration for {} recreated",
+                    self.webhook.name_any()
+                );
+                self.webhook_resource = Some(webhook);
+                return Ok(());
+            }
+            Err(e) => return Err(Error::WebhookHelperCreationFailed(e)),
+        };
+
+        let stale = match (&desired, &live) {
+            (WebhookType::Validating(d), WebhookType::Validating(l)) if ca_only => {
+                let bundles = |h: &ValidatingWebhookConfiguration| -> Vec<Option<ByteString>> {
+                    h.webhooks
+                        .iter()
+                        .flatten()
+                        .map(|w| w.client_config.ca_bundle.clone())
+                        .collect()
+                };
+                bundles(d) != bundles(l)
+            }
+            (WebhookType::Mutating(d), WebhookType::Mutating(l)) if ca_only => {
+                let bundles = |h: &MutatingWebhookConfiguration| -> Vec<Option<ByteString>> {
+                    h.webhooks
+                        .iter()
+                        .flatten()
+                        .map(|w| w.client_config.ca_bundle.clone())
+                        .collect()
+                };
+                bundles(d) != bundles(l)
+            }
+            (WebhookType::Validating(d), WebhookType::Validating(l)) => spec_changed(d, l),
+            (WebhookType::Mutating(d), WebhookType::Mutating(l)) => spec_changed(d, l),
+            _ => true,
+        };
+        if !stale {
+            self.webhook_resource = Some(live);
+            return Ok(());
+        };
+
+        let webhook = self.apply_webhook(desired).await?;
+        match &webhook {
+            WebhookType::Validating(h) => {
+                info!("ValidatingWebhookConfiguration {} updated", h.name_any())
+            }
+            WebhookType::Mutating(h) => {
+                info!("MutatingWebhookConfiguration {} updated", h.name_any())
             }
+        };
+        self.webhook_resource = Some(webhook);
+        Ok(())
+    }
+
+    async fn apply_webhook(&self, webhook: WebhookType) -> Result<WebhookType, Error> {
+        let operation = apply_operation(&self.webhook);
+        Ok(match webhook {
+            WebhookType::Mutating(hook) => WebhookType::Mutating(
+                perform_cluster_operation(self.client.clone(), operation, &hook).await?,
+            ),
+            WebhookType::Validating(hook) => WebhookType::Validating(
+                perform_cluster_operation(self.client.clone(), operation, &hook).await?,
+            ),
+        })
+    }
+
+    /// The webhook configuration as the spec describes it, pointed at the helper's service
+    async fn desired_webhook(&self) -> Result<WebhookType, Error> {
+        let cluster_ca_string = self.ca_bundle().await?;
+        let client_config = WebhookClientConfig {
+            ca_bundle: Some(ByteString(cluster_ca_string.as_bytes().into())),
+            service: Some(ServiceReference {
+                name: service_name(&self.webhook)?,
+                namespace: self.webhook.spec.namespace.clone(),
+                port: Some(self.webhook.spec.listening_port),
+                path: self.webhook.spec.path.clone(),
+            }),
+            url: None,
+        };
+
+        if let Ok(mut hook) = convert_to_mutating_webhook(self.webhook.spec.webhook.clone()).await {
+            for w in hook.webhooks.iter_mut().flatten() {
+                w.client_config = client_config.clone();
+            }
+            set_managed_by(&mut hook);
+            set_owner(&mut hook, &self.webhook);
+            stamp_spec_hash(&mut hook)?;
+            Ok(WebhookType::Mutating(hook))
+        } else if let Ok(mut hook) =
+            convert_to_admission_webhook(self.webhook.spec.webhook.clone()).await
+        {
+            for w in hook.webhooks.iter_mut().flatten() {
+                w.client_config = client_config.clone();
+            }
+            set_managed_by(&mut hook);
+            set_owner(&mut hook, &self.webhook);
+            stamp_spec_hash(&mut hook)?;
+            Ok(WebhookType::Validating(hook))
         } else {
             Err(Error::UnknownOperation(
                 "Unable to determine webhook type".into(),
@@ -218,6 +273,27 @@ impl WebhookStage {
     }
 }
 
+/// CA of a provided secret or the one handed out by the issuer, falling back to the cluster root CA
+pub async fn ca_bundle(client: Client, webhook: &WebhookHelper) -> Result<String, Error> {
+    if let Some(tls_secret) = webhook.spec.tls_secret.as_ref() {
+        return provided_ca(client, webhook, tls_secret).await;
+    };
+
+    if let Ok(secret) = perform_get::<Secret>(
+        client.clone(),
+        &webhook.name_any().to_lowercase(),
+        &webhook.spec.namespace,
+    )
+    .await
+    {
+        if let Some(ca) = secret.data.and_then(|d| d.get("ca.crt").cloned()) {
+            return Ok(String::from_utf8_lossy(&ca.0).to_string());
+        };
+    };
+
+    cluster_ca(client).await
+}
+
 async fn convert_to_mutating_webhook(data: Value) -> Result<MutatingWebhookConfiguration, Error> {
     let value: MutatingWebhookConfiguration = serde_json::from_value(data)?;
     Ok(value)